@@ -0,0 +1,21 @@
+//! Renders on demand and wakes the event loop from another thread once a second to redraw.
+//! Run with `cargo run --example user_event`.
+
+use std::{thread, time::Duration};
+
+use winit_wgpu_min::{RenderMode, UserEvent, WinitWrapper};
+
+fn main() {
+    let event_loop = winit_wgpu_min::event_loop().unwrap();
+    let proxy = event_loop.create_proxy();
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        // Fails once the event loop is gone.
+        if proxy.send_event(UserEvent::Redraw).is_err() {
+            break;
+        }
+    });
+    let mut winit_wrapper = WinitWrapper::default();
+    winit_wrapper.set_render_mode(RenderMode::OnDemand);
+    winit_wgpu_min::run_on(event_loop, winit_wrapper).unwrap();
+}
@@ -0,0 +1,84 @@
+use crate::State;
+
+/// How the built-in scene pipelines combine their output with the color target, see
+/// `State::set_blend`. Also usable for own pipelines with `blend_state`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlendPreset {
+    /// Overwrites the target, the fastest and the default.
+    #[default]
+    Opaque,
+    /// Mixes by the output's alpha, for translucent shapes with straight alpha. Draw them
+    /// back to front, they don't mix correctly otherwise.
+    AlphaBlend,
+    /// Adds the output, weighted by its alpha, e.g. for light, fire or particles. The order
+    /// doesn't matter.
+    Additive,
+    /// Like `AlphaBlend`, for output whose color is already multiplied by its alpha, which
+    /// avoids dark fringes around filtered edges.
+    PremultipliedAlpha,
+}
+
+impl BlendPreset {
+    pub fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            BlendPreset::Opaque => wgpu::BlendState::REPLACE,
+            BlendPreset::AlphaBlend => wgpu::BlendState::ALPHA_BLENDING,
+            BlendPreset::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            BlendPreset::PremultipliedAlpha => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
+    }
+}
+
+impl From<BlendPreset> for wgpu::BlendState {
+    fn from(preset: BlendPreset) -> Self {
+        preset.blend_state()
+    }
+}
+
+impl State {
+    pub fn blend(&self) -> BlendPreset {
+        self.blend
+    }
+
+    /// Sets how the triangle, the mesh and the vertices of `set_vertex_storage` are blended
+    /// into the frame, `BlendPreset::Opaque` by default. Recreates their pipelines.
+    pub fn set_blend(&mut self, preset: BlendPreset) {
+        self.blend = preset;
+        self.recreate_pipelines();
+        self.request_redraw();
+    }
+
+    pub fn write_mask(&self) -> wgpu::ColorWrites {
+        self.write_mask
+    }
+
+    /// Sets which color channels the triangle, the mesh and the vertices of
+    /// `set_vertex_storage` write, all by default. E.g. `ColorWrites::COLOR` keeps the alpha
+    /// of the target. Recreates their pipelines.
+    pub fn set_write_mask(&mut self, write_mask: wgpu::ColorWrites) {
+        self.write_mask = write_mask;
+        self.recreate_pipelines();
+        self.request_redraw();
+    }
+
+    /// The color target of the built-in scene pipelines with the surface format, blend and
+    /// write mask, for own pipelines that should draw like them.
+    pub fn color_target_state(&self) -> wgpu::ColorTargetState {
+        wgpu::ColorTargetState {
+            format: self.surface_config.format,
+            blend: Some(self.blend.blend_state()),
+            write_mask: self.write_mask,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use wgpu::util::DeviceExt;
+
+/// The camera data as it is laid out in the uniform buffer at `@group(0) @binding(0)`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraUniform {
+    /// Column-major view-projection matrix, e.g. from glam's `Mat4::to_cols_array_2d`.
+    pub view_proj: [[f32; 4]; 4],
+}
+
+// SAFETY: `CameraUniform` is `repr(C)`, only contains `f32`s and has no padding.
+unsafe impl bytemuck::Zeroable for CameraUniform {}
+unsafe impl bytemuck::Pod for CameraUniform {}
+
+impl CameraUniform {
+    pub const IDENTITY: Self = Self {
+        view_proj: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+}
+
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// The uniform buffer and bind group holding the `CameraUniform`.
+pub(crate) struct Camera {
+    pub buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Camera {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::bytes_of(&CameraUniform::IDENTITY),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::{io, path::Path};
+
+use crate::{png, readback, render_context::FrameTarget, CaptureError, State};
+
+/// A frame read back from the GPU as tightly packed 8 bit RGBA pixels, row by row from the top.
+/// Not an `image::RgbaImage`, see the crate docs for converting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl CapturedFrame {
+    /// The RGBA value of the pixel at `x`, `y`.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.rgba[i..i + 4].try_into().unwrap()
+    }
+
+    pub fn encode_png(&self) -> Vec<u8> {
+        png::encode_rgba(self.width, self.height, &self.rgba)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.encode_png())
+    }
+}
+
+impl State {
+    /// Renders a frame into an offscreen texture and reads it back, the window is left untouched.
+    /// The frame is recorded like in `render`, with the render function, the render graph and
+    /// FXAA, see `set_render_fn`. With MSAA and FXAA the capture gets its own intermediate
+    /// textures, so frames loading the previous one aren't drawn on top of the capture. The
+    /// capture writes no GPU timestamps or occlusion queries.
+    pub async fn capture_frame(&mut self) -> Result<CapturedFrame, CaptureError> {
+        let format = self.surface_config.format;
+        let is_bgra = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            _ => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+        let width = self.surface_config.width.max(1);
+        let height = self.surface_config.height.max(1);
+        // The pipelines target the surface format, so the capture texture uses it as well.
+        let texture = self.gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // `None` without MSAA or FXAA, the frame is then drawn into `texture` directly.
+        let msaa_view =
+            Self::create_msaa_view(&self.gpu.device, &self.surface_config, self.sample_count);
+        let fxaa_target = self
+            .fxaa
+            .as_ref()
+            .map(|fxaa| fxaa.create_target(&self.gpu.device, &self.surface_config));
+
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row = readback::padded_bytes_per_row(unpadded_bytes_per_row);
+        let buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+        self.upload_frame_data(&mut encoder);
+        // Taken out for the call, like in `render`.
+        let mut render_fn = std::mem::replace(&mut self.render_fn, Box::new(|_| {}));
+        let mut render_graph = std::mem::take(&mut self.render_graph);
+        self.run_render_fn(
+            &mut render_fn,
+            &mut render_graph,
+            &mut encoder,
+            FrameTarget {
+                texture: &texture,
+                view: &view,
+                msaa_view: msaa_view.as_ref(),
+                fxaa: fxaa_target.as_ref(),
+                queries: false,
+            },
+        );
+        self.render_fn = render_fn;
+        self.render_graph = render_graph;
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        // Like in `submit_frame`, without resolving queries or counting the frame.
+        self.staging_belt.finish();
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.staging_belt.recall();
+
+        let slice = buffer.slice(..);
+        readback::map_read(&self.gpu.device, slice).await?;
+        let mut rgba = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            // Strip the padding at the end of every row.
+            for row in data.chunks_exact(padded_bytes_per_row as usize) {
+                rgba.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+        if is_bgra {
+            for pixel in rgba.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Ok(CapturedFrame {
+            width,
+            height,
+            rgba,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+/// An additional render target the scene is drawn into before the surface, e.g. for a bloom
+/// prepass. It needs the size, format and sample count of the surface, because it shares the
+/// pipelines and the depth buffer with the main pass.
+#[derive(Debug)]
+pub struct ColorTarget {
+    pub view: wgpu::TextureView,
+    /// Where a multisampled `view` is resolved to.
+    pub resolve_target: Option<wgpu::TextureView>,
+    pub load: wgpu::LoadOp<wgpu::Color>,
+}
+
+impl ColorTarget {
+    pub(crate) fn attachment(&self) -> wgpu::RenderPassColorAttachment<'_> {
+        wgpu::RenderPassColorAttachment {
+            view: &self.view,
+            resolve_target: self.resolve_target.as_ref(),
+            ops: wgpu::Operations {
+                load: self.load,
+                store: wgpu::StoreOp::Store,
+            },
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use wgpu::util::DeviceExt;
+
+use crate::{readback, GpuContext};
+
+/// A compute pipeline with one storage buffer, e.g. for simulations on the GPU.
+/// The shader accesses the buffer as `@group(0) @binding(0) var<storage, read_write>`.
+pub struct ComputePass {
+    gpu: Arc<GpuContext>,
+    pipeline: wgpu::ComputePipeline,
+    storage_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl ComputePass {
+    /// Creates the pipeline from the WGSL source and a storage buffer holding `contents`.
+    /// Panics if `contents` is empty, wgpu doesn't bind empty buffers.
+    pub fn new(gpu: Arc<GpuContext>, wgsl: &str, entry_point: &str, contents: &[u8]) -> Self {
+        assert!(
+            !contents.is_empty(),
+            "the storage buffer of a compute pass can't be empty"
+        );
+        let device = &gpu.device;
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(wgsl.into()),
+        });
+        // The layout is derived from the shader, so it only has to declare the storage buffer.
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compute Pipeline"),
+            layout: None,
+            module: &shader,
+            entry_point,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+        let storage_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Storage Buffer"),
+            contents,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Compute Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: storage_buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            gpu,
+            pipeline,
+            storage_buffer,
+            bind_group,
+        }
+    }
+
+    /// The storage buffer, e.g. to bind it in a render pipeline as well.
+    pub fn storage_buffer(&self) -> &wgpu::Buffer {
+        &self.storage_buffer
+    }
+
+    /// Overwrites the start of the storage buffer, takes effect on the next dispatch.
+    pub fn write(&self, data: &[u8]) {
+        self.gpu.queue.write_buffer(&self.storage_buffer, 0, data);
+    }
+
+    /// Runs the shader with `x * y * z` workgroups and submits it right away.
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Compute Encoder"),
+            });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Compute Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &self.bind_group, &[]);
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Copies the storage buffer back to the CPU after all submitted dispatches finished.
+    pub async fn read(&self) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+        let size = self.storage_buffer.size();
+        // Storage buffers can't be mapped, so the data goes through a staging buffer.
+        let staging_buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compute Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Compute Readback Encoder"),
+            });
+        encoder.copy_buffer_to_buffer(&self.storage_buffer, 0, &staging_buffer, 0, size);
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        readback::map_read(&self.gpu.device, slice).await?;
+        let data = slice.get_mapped_range().to_vec();
+        staging_buffer.unmap();
+        Ok(data)
+    }
+}
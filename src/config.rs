@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+/// Options used by `State::new_with_config` to set up the adapter and device.
+/// `StateConfig::default()` gives the same setup as `State::new`.
+#[derive(Clone, Debug)]
+pub struct StateConfig {
+    /// The backends wgpu may use, the `WGPU_BACKEND` environment variable (e.g. `vulkan`,
+    /// `metal`, `dx12`, `gl` or a comma separated list) overrides this when set.
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    pub force_fallback_adapter: bool,
+    pub required_features: wgpu::Features,
+    /// Requested where the adapter has them, unlike `required_features`, without which
+    /// creating the device fails. E.g. `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES` allows the
+    /// MSAA sample counts 2, 8 and 16 where the adapter supports them. Empty by default, the
+    /// `gpu-timing` feature adds `TIMESTAMP_QUERY`.
+    pub optional_features: wgpu::Features,
+    pub required_limits: wgpu::Limits,
+    /// How the device should trade memory usage for speed, `Performance` by default.
+    pub memory_hints: wgpu::MemoryHints,
+    /// Picks an sRGB surface format if there is one, otherwise a linear one, e.g. for doing
+    /// the gamma correction in the shader. Falls back to the first supported format.
+    pub prefer_srgb: bool,
+    /// Picks a premultiplied or postmultiplied alpha mode if the surface supports one, so the
+    /// alpha of the clear color shows through. The window also needs
+    /// `WindowAttributes::with_transparent(true)`, see `State::set_transparent`.
+    pub transparent: bool,
+    /// Picks the `Rgba16Float` HDR surface format if the surface supports it, see
+    /// `State::color_space`. Falls back to the SDR format picked by `prefer_srgb`.
+    pub request_hdr: bool,
+    /// Other formats views of the surface texture may have, see `State::set_view_formats`.
+    pub view_formats: Vec<wgpu::TextureFormat>,
+    /// A directory wgpu records a replayable API trace into, e.g. for wgpu bug reports. Only
+    /// works if the application enables wgpu's `trace` feature, e.g. with
+    /// `wgpu = { version = "22", features = ["trace"] }`, otherwise wgpu logs an error and
+    /// records nothing. Ignored in the browser.
+    pub trace_path: Option<PathBuf>,
+    /// How the surface textures, or the texture of headless mode, can be used besides being
+    /// rendered to, e.g. `COPY_SRC` to copy frames off the surface in own render functions.
+    /// Creating the state fails if the surface or format doesn't support it.
+    /// `RENDER_ATTACHMENT` by default, which is always added.
+    pub surface_usage: wgpu::TextureUsages,
+    /// The debug label of the device, e.g. to tell the devices of several adapters apart in
+    /// RenderDoc, PIX or Xcode captures. wgpu can't label surfaces, the frames of a window can
+    /// be named with `State::set_label_prefix`.
+    pub device_label: Option<String>,
+    /// The MSAA sample count the state starts with, see `State::set_sample_count`. The depth
+    /// texture gets the same count. 1 (no MSAA) by default.
+    pub sample_count: u32,
+}
+
+impl StateConfig {
+    /// Forces a software adapter like lavapipe, WARP or llvmpipe, e.g. for CI without a GPU
+    /// or reproducible output. Only the downlevel limits are required, which is all some of
+    /// them support.
+    pub fn software() -> Self {
+        Self {
+            // Metal has no software adapter.
+            #[cfg(not(target_arch = "wasm32"))]
+            backends: wgpu::Backends::VULKAN | wgpu::Backends::DX12 | wgpu::Backends::GL,
+            force_fallback_adapter: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            ..Self::default()
+        }
+    }
+
+    /// `backends`, unless overridden by the `WGPU_BACKEND` environment variable. A value
+    /// naming no known backend is ignored.
+    pub fn effective_backends(&self) -> wgpu::Backends {
+        std::env::var("WGPU_BACKEND")
+            .ok()
+            .map(|value| parse_backends(&value))
+            .filter(|backends| !backends.is_empty())
+            .unwrap_or(self.backends)
+    }
+}
+
+/// Parses a backend name like `vulkan` or a comma separated list of them, unknown names are
+/// skipped.
+fn parse_backends(value: &str) -> wgpu::Backends {
+    wgpu::util::parse_backends_from_comma_list(value)
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            // On wasm only WebGL2 is targeted, so only the GL backend is enabled.
+            #[cfg(not(target_arch = "wasm32"))]
+            backends: wgpu::Backends::all(),
+            #[cfg(target_arch = "wasm32")]
+            backends: wgpu::Backends::GL,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
+            // WebGL2 doesn't support all of wgpu's default limits.
+            #[cfg(not(target_arch = "wasm32"))]
+            required_limits: wgpu::Limits::default(),
+            #[cfg(target_arch = "wasm32")]
+            required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
+            memory_hints: wgpu::MemoryHints::Performance,
+            prefer_srgb: true,
+            transparent: false,
+            request_hdr: false,
+            view_formats: Vec::new(),
+            trace_path: None,
+            surface_usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            device_label: None,
+            sample_count: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_names_are_parsed() {
+        assert_eq!(parse_backends("vulkan"), wgpu::Backends::VULKAN);
+        assert_eq!(parse_backends("metal"), wgpu::Backends::METAL);
+        assert_eq!(parse_backends("dx12"), wgpu::Backends::DX12);
+        assert_eq!(parse_backends("gl"), wgpu::Backends::GL);
+    }
+
+    #[test]
+    fn backend_lists_are_parsed() {
+        assert_eq!(
+            parse_backends("Vulkan, GL"),
+            wgpu::Backends::VULKAN | wgpu::Backends::GL
+        );
+        assert_eq!(parse_backends("metal,nope"), wgpu::Backends::METAL);
+        assert_eq!(parse_backends("nope"), wgpu::Backends::empty());
+    }
+}
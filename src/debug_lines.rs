@@ -0,0 +1,161 @@
+use crate::{pipeline::TargetFormats, State, Vertex};
+
+/// The lines of `State::draw_line` and the pipeline drawing them.
+pub(crate) struct DebugLines {
+    pipeline: wgpu::RenderPipeline,
+    /// Only grows, like the mesh instance buffer.
+    buffer: wgpu::Buffer,
+    /// How many vertices fit into `buffer`.
+    capacity: usize,
+    /// Two vertices per line, drawn since the last upload.
+    pending: Vec<Vertex>,
+    /// The number of uploaded vertices.
+    count: u32,
+}
+
+impl DebugLines {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_layout: &wgpu::BindGroupLayout,
+        targets: TargetFormats,
+    ) -> Self {
+        Self {
+            pipeline: create_pipeline(device, camera_layout, targets),
+            buffer: Self::create_buffer(device, 2),
+            capacity: 2,
+            pending: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Recreates the pipeline after the targets changed, the lines are kept.
+    pub fn recreate_pipeline(
+        &mut self,
+        device: &wgpu::Device,
+        camera_layout: &wgpu::BindGroupLayout,
+        targets: TargetFormats,
+    ) {
+        self.pipeline = create_pipeline(device, camera_layout, targets);
+    }
+
+    pub fn push(&mut self, a: Vertex, b: Vertex) {
+        self.pending.extend([a, b]);
+    }
+
+    /// Uploads the lines drawn since the last upload for the next `draw` through the frame's
+    /// staging belt and starts collecting the next frame.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        if self.pending.len() > self.capacity {
+            self.capacity = self.pending.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+        let data: &[u8] = bytemuck::cast_slice(&self.pending);
+        if let Some(size) = wgpu::BufferSize::new(data.len() as u64) {
+            staging_belt
+                .write_buffer(encoder, &self.buffer, 0, size, device)
+                .copy_from_slice(data);
+        }
+        self.count = self.pending.len() as u32;
+        self.pending.clear();
+    }
+
+    /// Draws the uploaded lines, the camera has to be bound at group 0. Without lines nothing
+    /// is drawn.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        render_pass.draw(0..self.count, 0..1);
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Line Buffer"),
+            size: (capacity * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+/// Creates the pipeline drawing the lines, they are hidden behind the scene's geometry but
+/// don't hide each other.
+fn create_pipeline(
+    device: &wgpu::Device,
+    camera_layout: &wgpu::BindGroupLayout,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/debug_lines.wgsl"));
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Debug Line Pipeline Layout"),
+        bind_group_layouts: &[camera_layout],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Debug Line Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[Vertex::LAYOUT],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: targets.color,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: targets.depth,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: targets.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+impl State {
+    /// Draws a line from `a` to `b` in the next frame, e.g. for bounding boxes, grids or
+    /// vectors. The ends are transformed by the camera like the mesh, and the line is hidden
+    /// behind the scene's geometry. Call it every frame the line should be visible, e.g.
+    /// before `render`. In `RenderMode::OnDemand` a redraw has to be requested.
+    pub fn draw_line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 3]) {
+        let targets = self.target_formats();
+        self.debug_lines
+            .get_or_insert_with(|| {
+                DebugLines::new(&self.gpu.device, &self.camera.bind_group_layout, targets)
+            })
+            .push(Vertex { position: a, color }, Vertex { position: b, color });
+    }
+
+    /// Uploads the lines drawn since the last frame.
+    pub(crate) fn upload_debug_lines(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(debug_lines) = &mut self.debug_lines {
+            debug_lines.upload(&self.gpu.device, &mut self.staging_belt, encoder);
+        }
+    }
+}
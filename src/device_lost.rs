@@ -0,0 +1,199 @@
+#[cfg(not(target_arch = "wasm32"))]
+use pollster::FutureExt;
+use std::sync::Arc;
+
+use wgpu::Surface;
+use winit::event_loop::ActiveEventLoop;
+
+use crate::{
+    logging::{log_error, log_info, log_warn},
+    GpuContext, State, StateError,
+};
+
+impl State {
+    /// Calls `callback` after the device was lost and everything was recreated, e.g. to upload
+    /// the meshes, instances, textures and camera again. Color targets and occlusion queries
+    /// are removed, since they belong to the old device.
+    pub fn on_device_lost(&mut self, callback: impl FnMut(&mut State) + 'static) {
+        self.device_lost_callback = Some(Box::new(callback));
+    }
+
+    /// Destroys the device as if the driver had reset, for testing the recovery. The state
+    /// recreates it in the next `about_to_wait`.
+    pub fn simulate_device_lost(&self) {
+        self.gpu.device.destroy();
+    }
+
+    /// Recreates the instance, adapter, device and surface with the config they were created
+    /// with, and everything created on them. Done automatically when the device is lost.
+    /// If it fails the state keeps its old device, with a new surface on it unless the device
+    /// is lost.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recreate_gpu(&mut self) -> Result<(), StateError> {
+        self.recreate_gpu_async().block_on()
+    }
+
+    /// Async version of `State::recreate_gpu`, the browser can't block on the adapter and device requests.
+    pub async fn recreate_gpu_async(&mut self) -> Result<(), StateError> {
+        let result = self.try_recreate_gpu().await;
+        if result.is_err() {
+            self.restore_surface();
+        }
+        result
+    }
+
+    async fn try_recreate_gpu(&mut self) -> Result<(), StateError> {
+        let mut config = self.gpu.config.clone();
+        let instance = GpuContext::create_instance(&config);
+        // The old surface has to be gone before a new one is created for the same window.
+        self.surface = None;
+        let surface = match &self.window {
+            Some(window) => Some(Self::create_surface(&instance, window)?),
+            None => None,
+        };
+        let adapter = GpuContext::request_adapter(&instance, &mut config, surface.as_ref()).await?;
+        let gpu = GpuContext::with_adapter(instance, adapter, &config).await?;
+        self.rebuild(Arc::new(gpu), surface)
+    }
+
+    /// Moves the state to another device, e.g. the one another window recreated with
+    /// `recreate_gpu`, and recreates everything on it. If it fails the state keeps its old
+    /// device like with `recreate_gpu`.
+    pub fn replace_gpu(&mut self, gpu: Arc<GpuContext>) -> Result<(), StateError> {
+        let result = self.try_replace_gpu(gpu);
+        if result.is_err() {
+            self.restore_surface();
+        }
+        result
+    }
+
+    fn try_replace_gpu(&mut self, gpu: Arc<GpuContext>) -> Result<(), StateError> {
+        self.surface = None;
+        let surface = match &self.window {
+            Some(window) => {
+                let surface = Self::create_surface(&gpu.instance, window)?;
+                if !gpu.adapter.is_surface_supported(&surface) {
+                    return Err(StateError::IncompatibleAdapter);
+                }
+                Some(surface)
+            }
+            None => None,
+        };
+        self.rebuild(gpu, surface)
+    }
+
+    /// Creates a surface on the old device again after moving to a new one failed, the old
+    /// surface had to be dropped first. Not on a lost device, configuring a surface on it
+    /// would panic. Nothing is rendered without a surface until the device is recreated.
+    fn restore_surface(&mut self) {
+        if self.surface.is_some() || self.gpu.is_lost() {
+            return;
+        }
+        if let Err(err) = self.reconfigure_surface() {
+            log_warn!("Failed to restore the surface: {err}");
+        }
+    }
+
+    /// Swaps in everything created on `gpu` and keeps the settings that don't depend on it.
+    fn rebuild(
+        &mut self,
+        gpu: Arc<GpuContext>,
+        surface: Option<Surface<'static>>,
+    ) -> Result<(), StateError> {
+        let fresh = match (surface, &self.window) {
+            (Some(surface), Some(window)) => {
+                Self::with_surface(gpu, window.clone(), surface, true)?
+            }
+            _ => Self::from_parts(gpu, None, self.surface_config.clone(), None),
+        };
+        let State {
+            gpu,
+            surface,
+            surface_config,
+            surface_caps,
+            headless_target,
+            depth_texture,
+            depth_view,
+            msaa_view,
+            #[cfg(feature = "triangle")]
+            render_pipeline,
+            mesh_pipeline,
+            instances,
+            camera,
+            frame_uniforms,
+            #[cfg(feature = "gpu-timing")]
+            gpu_timer,
+            ..
+        } = fresh;
+        let old_config = std::mem::replace(&mut self.surface_config, surface_config);
+        let fxaa = self.fxaa.take().is_some();
+        self.gpu = gpu;
+        self.surface = surface;
+        self.surface_caps = surface_caps;
+        self.headless_target = headless_target;
+        self.depth_texture = depth_texture;
+        self.depth_view = depth_view;
+        self.msaa_view = msaa_view;
+        #[cfg(feature = "triangle")]
+        {
+            self.render_pipeline = render_pipeline;
+        }
+        self.mesh_pipeline = mesh_pipeline;
+        self.mesh = None;
+        self.vertex_storage = None;
+        self.debug_lines = None;
+        self.sprite_batch = None;
+        // The font is kept, its glyphs are rasterized again into a new atlas.
+        #[cfg(feature = "text")]
+        if let Some(text) = self.text.take() {
+            self.text = Some(text.recreate(self));
+        }
+        self.instances = instances;
+        self.camera = camera;
+        self.frame_uniforms = frame_uniforms;
+        // The belt's buffers belong to the old device as well.
+        self.staging_belt = wgpu::util::StagingBelt::new(self.staging_chunk_size);
+        self.color_targets.clear();
+        self.occlusion_queries = None;
+        #[cfg(feature = "gpu-timing")]
+        {
+            self.gpu_timer = gpu_timer;
+        }
+
+        // Keep the surface settings where the new adapter supports them, setting the format
+        // also recreates the pipelines and render targets with the old sample count.
+        if !self.set_surface_format(old_config.format) {
+            self.set_sample_count(self.sample_count);
+        }
+        self.set_view_formats(old_config.view_formats);
+        self.set_present_mode(old_config.present_mode);
+        self.set_alpha_mode(old_config.alpha_mode);
+        self.set_frame_latency(self.frame_latency);
+        self.set_fxaa(fxaa);
+        log_info!("Recreated the GPU resources");
+
+        if let Some(mut callback) = self.device_lost_callback.take() {
+            callback(self);
+            // The callback may have replaced itself.
+            self.device_lost_callback.get_or_insert(callback);
+        }
+        self.request_redraw();
+        Ok(())
+    }
+
+    /// Recreates the lost device, exits if that fails.
+    pub(crate) fn recover_from_device_loss(&mut self, event_loop: &ActiveEventLoop) {
+        log_warn!("Recreating the lost device");
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(err) = self.recreate_gpu() {
+            log_error!("Failed to recreate the device: {err}");
+            event_loop.exit();
+        }
+        // Blocking on the adapter request isn't possible in the browser.
+        #[cfg(target_arch = "wasm32")]
+        {
+            log_error!("The device was lost, it can't be recreated in the browser");
+            event_loop.exit();
+        }
+    }
+}
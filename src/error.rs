@@ -0,0 +1,432 @@
+use std::{error, fmt};
+
+/// Everything that can go wrong while creating a `State`.
+#[derive(Debug)]
+pub enum StateError {
+    /// The window couldn't be created, see `WinitWrapper::open_window`.
+    CreateWindow(winit::error::OsError),
+    /// The window didn't provide a raw window or display handle.
+    WindowHandle(wgpu::rwh::HandleError),
+    CreateSurface(wgpu::CreateSurfaceError),
+    /// No adapter is compatible with the surface and the requested options.
+    NoAdapter,
+    /// The adapter passed to `State::new_with_adapter` can't present to the window.
+    IncompatibleAdapter,
+    /// The surface supports no format that can be rendered to.
+    NoSupportedFormat,
+    /// The surface, or the texture of headless mode, doesn't support the
+    /// `StateConfig::surface_usage` with the rendering usage.
+    UnsupportedSurfaceUsage(wgpu::TextureUsages),
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateWindow(e) => write!(f, "failed to create the window: {e}"),
+            Self::WindowHandle(e) => write!(f, "failed to get the window handle: {e}"),
+            Self::CreateSurface(e) => write!(f, "failed to create the surface: {e}"),
+            Self::NoAdapter => write!(f, "no suitable adapter found"),
+            Self::IncompatibleAdapter => write!(f, "the adapter can't present to the window"),
+            Self::NoSupportedFormat => write!(f, "the surface supports no renderable format"),
+            Self::UnsupportedSurfaceUsage(usage) => {
+                write!(f, "the surface doesn't support the usage {usage:?}")
+            }
+            Self::RequestDevice(e) => write!(f, "failed to request the device: {e}"),
+        }
+    }
+}
+
+impl error::Error for StateError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::CreateWindow(e) => Some(e),
+            Self::WindowHandle(e) => Some(e),
+            Self::CreateSurface(e) => Some(e),
+            Self::NoAdapter
+            | Self::IncompatibleAdapter
+            | Self::NoSupportedFormat
+            | Self::UnsupportedSurfaceUsage(_) => None,
+            Self::RequestDevice(e) => Some(e),
+        }
+    }
+}
+
+impl From<winit::error::OsError> for StateError {
+    fn from(e: winit::error::OsError) -> Self {
+        Self::CreateWindow(e)
+    }
+}
+
+impl From<wgpu::rwh::HandleError> for StateError {
+    fn from(e: wgpu::rwh::HandleError) -> Self {
+        Self::WindowHandle(e)
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for StateError {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        Self::CreateSurface(e)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for StateError {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        Self::RequestDevice(e)
+    }
+}
+
+/// Everything that can go wrong in `State::capture_frame`.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// Only 8 bit RGBA and BGRA surface formats can be captured.
+    UnsupportedFormat(wgpu::TextureFormat),
+    BufferMap(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => write!(f, "can't capture format {format:?}"),
+            Self::BufferMap(e) => write!(f, "failed to map the capture buffer: {e}"),
+        }
+    }
+}
+
+impl error::Error for CaptureError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::UnsupportedFormat(_) => None,
+            Self::BufferMap(e) => Some(e),
+        }
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for CaptureError {
+    fn from(e: wgpu::BufferAsyncError) -> Self {
+        Self::BufferMap(e)
+    }
+}
+
+/// Why `RenderGraph::add_pass` rejected a pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderGraphError {
+    /// There already is a pass with this name.
+    DuplicatePass(String),
+    /// A dependency that isn't in the graph yet.
+    UnknownDependency(String),
+}
+
+impl fmt::Display for RenderGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicatePass(name) => write!(f, "there already is a pass named {name:?}"),
+            Self::UnknownDependency(name) => write!(f, "no pass named {name:?} to depend on"),
+        }
+    }
+}
+
+impl error::Error for RenderGraphError {}
+
+/// Why `State::create_render_pipeline` rejected a pipeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineError {
+    /// The pipeline's `MultisampleState::count` differs from `State::sample_count`.
+    SampleCountMismatch { pipeline: u32, state: u32 },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SampleCountMismatch { pipeline, state } => write!(
+                f,
+                "the pipeline has a sample count of {pipeline}, but the passes use {state}, \
+                 see State::sample_count"
+            ),
+        }
+    }
+}
+
+impl error::Error for PipelineError {}
+
+/// Why `State::set_vertex_storage` rejected the vertices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VertexStorageError {
+    /// The device can't read storage buffers in vertex shaders, e.g. on WebGL2.
+    Unsupported,
+    /// The data isn't a whole number of `Vertex`es long.
+    DataSize(usize),
+    /// The data is larger than a storage buffer binding may be.
+    TooLarge { size: u64, max: u64 },
+}
+
+impl fmt::Display for VertexStorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "vertex shaders can't read storage buffers"),
+            Self::DataSize(size) => write!(f, "{size} bytes aren't a whole number of vertices"),
+            Self::TooLarge { size, max } => {
+                write!(
+                    f,
+                    "{size} bytes of vertices exceed the storage buffer limit of {max}"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for VertexStorageError {}
+
+/// Everything that can go wrong in `State::load_texture`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextureError {
+    /// The width or height is zero, wgpu doesn't allow empty textures.
+    ZeroSize,
+    /// The pixel data isn't `width * height * 4` bytes long.
+    DataSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroSize => write!(f, "the texture has a zero width or height"),
+            Self::DataSize { expected, actual } => {
+                write!(f, "expected {expected} bytes of pixel data, got {actual}")
+            }
+        }
+    }
+}
+
+impl error::Error for TextureError {}
+
+/// Any error of this crate, for applications that handle them all the same way, e.g. with
+/// `?` in `main`. Every error a public method returns converts into it.
+///
+/// The methods still return the specific errors above, or those of wgpu and winit, so each
+/// signature says what can go wrong in it and callers can react to single cases without a
+/// catch-all arm. E.g. `State::render`'s `wgpu::SurfaceError::Lost` is recovered from by
+/// reconfiguring the surface, while other errors of this enum could never occur there.
+#[derive(Debug)]
+pub enum Error {
+    /// Creating the window, surface, adapter or device failed.
+    State(StateError),
+    Capture(CaptureError),
+    RenderGraph(RenderGraphError),
+    Pipeline(PipelineError),
+    VertexStorage(VertexStorageError),
+    Texture(TextureError),
+    /// Getting the surface texture failed, see `State::render`.
+    Surface(wgpu::SurfaceError),
+    /// Reading a buffer back failed, e.g. in `ComputePass::read`.
+    BufferMap(wgpu::BufferAsyncError),
+    /// The pixels passed to `State::set_icon_from_rgba` aren't a valid icon.
+    Icon(winit::window::BadIcon),
+    /// The window couldn't change the cursor grab, see `State::set_cursor_grabbed`.
+    CursorGrab(winit::error::ExternalError),
+    /// An I/O operation failed, e.g. writing the file in `CapturedFrame::save_png`.
+    Io(std::io::Error),
+    /// The data passed to `State::set_font` isn't a font.
+    #[cfg(feature = "text")]
+    Font(ab_glyph::InvalidFont),
+    /// The event loop couldn't be created or stopped with an error, see `run`.
+    EventLoop(winit::error::EventLoopError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::State(e) => e.fmt(f),
+            Self::Capture(e) => e.fmt(f),
+            Self::RenderGraph(e) => e.fmt(f),
+            Self::Pipeline(e) => e.fmt(f),
+            Self::VertexStorage(e) => e.fmt(f),
+            Self::Texture(e) => e.fmt(f),
+            Self::Surface(e) => write!(f, "failed to get the surface texture: {e}"),
+            Self::BufferMap(e) => write!(f, "failed to map the buffer: {e}"),
+            Self::Icon(e) => write!(f, "invalid window icon: {e}"),
+            Self::CursorGrab(e) => write!(f, "failed to change the cursor grab: {e}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            #[cfg(feature = "text")]
+            Self::Font(e) => write!(f, "failed to load the font: {e}"),
+            Self::EventLoop(e) => write!(f, "the event loop failed: {e}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    // The wrapped error's own source, since `Display` already shows the wrapped error.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::State(e) => e.source(),
+            Self::Capture(e) => e.source(),
+            Self::RenderGraph(e) => e.source(),
+            Self::Pipeline(e) => e.source(),
+            Self::VertexStorage(e) => e.source(),
+            Self::Texture(e) => e.source(),
+            Self::Surface(e) => Some(e),
+            Self::BufferMap(e) => Some(e),
+            Self::Icon(e) => Some(e),
+            Self::CursorGrab(e) => Some(e),
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "text")]
+            Self::Font(e) => Some(e),
+            Self::EventLoop(e) => Some(e),
+        }
+    }
+}
+
+impl From<StateError> for Error {
+    fn from(e: StateError) -> Self {
+        Self::State(e)
+    }
+}
+
+impl From<CaptureError> for Error {
+    fn from(e: CaptureError) -> Self {
+        Self::Capture(e)
+    }
+}
+
+impl From<RenderGraphError> for Error {
+    fn from(e: RenderGraphError) -> Self {
+        Self::RenderGraph(e)
+    }
+}
+
+impl From<PipelineError> for Error {
+    fn from(e: PipelineError) -> Self {
+        Self::Pipeline(e)
+    }
+}
+
+impl From<VertexStorageError> for Error {
+    fn from(e: VertexStorageError) -> Self {
+        Self::VertexStorage(e)
+    }
+}
+
+impl From<TextureError> for Error {
+    fn from(e: TextureError) -> Self {
+        Self::Texture(e)
+    }
+}
+
+impl From<wgpu::SurfaceError> for Error {
+    fn from(e: wgpu::SurfaceError) -> Self {
+        Self::Surface(e)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for Error {
+    fn from(e: wgpu::BufferAsyncError) -> Self {
+        Self::BufferMap(e)
+    }
+}
+
+impl From<winit::window::BadIcon> for Error {
+    fn from(e: winit::window::BadIcon) -> Self {
+        Self::Icon(e)
+    }
+}
+
+impl From<winit::error::ExternalError> for Error {
+    fn from(e: winit::error::ExternalError) -> Self {
+        Self::CursorGrab(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "text")]
+impl From<ab_glyph::InvalidFont> for Error {
+    fn from(e: ab_glyph::InvalidFont) -> Self {
+        Self::Font(e)
+    }
+}
+
+impl From<winit::error::EventLoopError> for Error {
+    fn from(e: winit::error::EventLoopError) -> Self {
+        Self::EventLoop(e)
+    }
+}
+
+impl From<winit::error::OsError> for Error {
+    fn from(e: winit::error::OsError) -> Self {
+        Self::State(e.into())
+    }
+}
+
+impl From<wgpu::rwh::HandleError> for Error {
+    fn from(e: wgpu::rwh::HandleError) -> Self {
+        Self::State(e.into())
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for Error {
+    fn from(e: wgpu::CreateSurfaceError) -> Self {
+        Self::State(e.into())
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for Error {
+    fn from(e: wgpu::RequestDeviceError) -> Self {
+        Self::State(e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn no_adapter_has_a_message_and_no_source() {
+        let err = StateError::NoAdapter;
+        assert_eq!(err.to_string(), "no suitable adapter found");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn unsupported_surface_usage_names_the_usage() {
+        let err = StateError::UnsupportedSurfaceUsage(wgpu::TextureUsages::STORAGE_BINDING);
+        assert_eq!(
+            err.to_string(),
+            "the surface doesn't support the usage TextureUsages(STORAGE_BINDING)"
+        );
+    }
+
+    #[test]
+    fn buffer_map_errors_are_not_capture_errors() {
+        let err = Error::from(wgpu::BufferAsyncError);
+        assert!(matches!(err, Error::BufferMap(_)));
+        assert!(err.source().is_some());
+        // Unless they come from a capture.
+        let err = Error::from(CaptureError::from(wgpu::BufferAsyncError));
+        assert!(matches!(err, Error::Capture(CaptureError::BufferMap(_))));
+    }
+
+    #[test]
+    fn window_errors_convert() {
+        let bad_icon = winit::window::Icon::from_rgba(vec![0; 3], 1, 1).unwrap_err();
+        let err = Error::from(bad_icon);
+        assert!(matches!(err, Error::Icon(_)));
+        assert!(err.to_string().starts_with("invalid window icon: "));
+        let err = Error::from(winit::error::ExternalError::Ignored);
+        assert!(matches!(err, Error::CursorGrab(_)));
+        assert!(err.source().is_some());
+    }
+    #[test]
+    fn io_errors_describe_any_operation() {
+        let err = Error::from(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "shader.wgsl not found",
+        ));
+        assert_eq!(err.to_string(), "I/O error: shader.wgsl not found");
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,71 @@
+use wgpu::util::DeviceExt;
+
+/// Shadertoy-style values for own shaders, updated every frame and laid out like in
+/// `FrameUniforms::WGSL` at `@group(1) @binding(0)`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameUniforms {
+    /// Seconds since the state was created. Wraps around every hour, an `f32` would lose the
+    /// sub-millisecond precision animations need after a few hours otherwise.
+    pub time: f32,
+    pub frame: u32,
+    /// The size of the frame in pixels.
+    pub resolution: [f32; 2],
+    /// The cursor position in 0..1 from the top left, -1 while it's outside the window.
+    pub mouse: [f32; 2],
+}
+
+// SAFETY: `FrameUniforms` is `repr(C)`, only contains 4 byte fields and has no padding.
+unsafe impl bytemuck::Zeroable for FrameUniforms {}
+unsafe impl bytemuck::Pod for FrameUniforms {}
+
+impl FrameUniforms {
+    /// The WGSL declaration of the uniforms, paste or concatenate it into a shader.
+    pub const WGSL: &'static str = include_str!("shaders/frame_uniforms.wgsl");
+
+    /// How often `time` wraps around, in seconds.
+    pub const TIME_WRAP: f64 = 3600.0;
+}
+
+/// The uniform buffer and bind group holding the `FrameUniforms`.
+pub(crate) struct FrameUniformBuffer {
+    pub buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl FrameUniformBuffer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Frame Uniform Buffer"),
+            contents: bytemuck::bytes_of(&FrameUniforms::default()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Frame Uniform Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Frame Uniform Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+}
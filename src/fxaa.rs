@@ -0,0 +1,212 @@
+use wgpu::SurfaceConfiguration;
+
+use crate::State;
+
+/// The offscreen texture the scene is rendered into with FXAA on, and the pass smoothing it
+/// into the frame, see `State::set_fxaa`.
+pub(crate) struct Fxaa {
+    pipeline: wgpu::RenderPipeline,
+    /// Shared by the pipeline and the bind groups, so either can be recreated on its own.
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    /// The target of the frames rendered to the window or the headless texture.
+    pub target: FxaaTarget,
+}
+
+/// An offscreen texture with the surface's size and format, and the bind group sampling it.
+pub(crate) struct FxaaTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Fxaa {
+    pub fn new(device: &wgpu::Device, surface_config: &SurfaceConfiguration) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("FXAA Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = create_pipeline(device, &bind_group_layout, surface_config.format);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("FXAA Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let target = create_target(device, surface_config, &bind_group_layout, &sampler);
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            target,
+        }
+    }
+
+    /// Recreates the pipeline after the surface format changed.
+    pub fn recreate_pipeline(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        self.pipeline = create_pipeline(device, &self.bind_group_layout, format);
+    }
+
+    /// Recreates the offscreen texture to match the surface size and format.
+    pub fn recreate_target(
+        &mut self,
+        device: &wgpu::Device,
+        surface_config: &SurfaceConfiguration,
+    ) {
+        self.target = self.create_target(device, surface_config);
+    }
+
+    /// Creates another offscreen texture, e.g. for captures, which shouldn't overwrite the
+    /// one of the frames.
+    pub fn create_target(
+        &self,
+        device: &wgpu::Device,
+        surface_config: &SurfaceConfiguration,
+    ) -> FxaaTarget {
+        create_target(
+            device,
+            surface_config,
+            &self.bind_group_layout,
+            &self.sampler,
+        )
+    }
+
+    /// Records the pass smoothing the offscreen texture of `target` into `view`.
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &FxaaTarget,
+        view: &wgpu::TextureView,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("FXAA Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    // Every pixel is overwritten.
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &target.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/fxaa.wgsl"));
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("FXAA Pipeline Layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("FXAA Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(format.into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Creates the offscreen texture with the surface's size and format. Sampling an sRGB
+/// texture returns linear colors and the sRGB frame encodes them again, so the colors come
+/// out unchanged.
+fn create_target(
+    device: &wgpu::Device,
+    surface_config: &SurfaceConfiguration,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+) -> FxaaTarget {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("FXAA Texture"),
+        size: wgpu::Extent3d {
+            width: surface_config.width.max(1),
+            height: surface_config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: surface_config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("FXAA Bind Group"),
+        layout: bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+    FxaaTarget {
+        texture,
+        view,
+        bind_group,
+    }
+}
+
+impl State {
+    pub fn fxaa(&self) -> bool {
+        self.fxaa.is_some()
+    }
+
+    /// Smooths the edges of the frame with FXAA, off by default. Unlike MSAA it works on
+    /// every device and costs the same for any scene, but it blurs fine details a bit. The
+    /// scene is rendered into an offscreen texture first, which the render function sees as
+    /// `RenderContext::texture`, so `view_with_format` only returns a view of the frame's
+    /// own format.
+    pub fn set_fxaa(&mut self, fxaa: bool) {
+        self.fxaa = fxaa.then(|| Fxaa::new(&self.gpu.device, &self.surface_config));
+        self.request_redraw();
+    }
+}
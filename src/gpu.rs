@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use pollster::FutureExt;
+use wgpu::{Adapter, Device, Instance, InstanceDescriptor, PowerPreference, Queue, Surface};
+
+use crate::{
+    logging::{log_debug, log_info, log_warn},
+    StateConfig, StateError,
+};
+
+/// The instance, adapter, device and queue, shared by the states of all windows through an
+/// `Arc`, so resources created on the device can be used in every window.
+pub struct GpuContext {
+    pub(crate) instance: Instance,
+    pub(crate) adapter: Adapter,
+    pub(crate) device: Device,
+    pub(crate) queue: Queue,
+    /// The limits the device was created with, `Device::limits` returns a copy every time.
+    limits: wgpu::Limits,
+    /// The config everything was created with, used to create it again when the device is lost.
+    pub(crate) config: StateConfig,
+    /// Set by the device lost callback.
+    lost: Arc<AtomicBool>,
+    /// The layouts of `get_or_create_bind_group_layout` by their label and entries.
+    bind_group_layouts: Mutex<HashMap<BindGroupLayoutKey, Arc<wgpu::BindGroupLayout>>>,
+}
+
+type BindGroupLayoutKey = (Option<String>, Vec<wgpu::BindGroupLayoutEntry>);
+
+impl GpuContext {
+    /// Requests any adapter matching `config`, see `State::new_with_gpu` to use it for a window.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(config: StateConfig) -> Result<Self, StateError> {
+        Self::new_async(config).block_on()
+    }
+
+    /// Async version of `GpuContext::new`, the browser can't block on the adapter and device requests.
+    pub async fn new_async(mut config: StateConfig) -> Result<Self, StateError> {
+        let instance = Self::create_instance(&config);
+        let adapter = Self::request_adapter(&instance, &mut config, None).await?;
+        Self::with_adapter(instance, adapter, &config).await
+    }
+
+    /// Requests the device and queue for an adapter from `instance`.
+    pub(crate) async fn with_adapter(
+        instance: Instance,
+        adapter: Adapter,
+        config: &StateConfig,
+    ) -> Result<Self, StateError> {
+        log_info!("Using adapter {:?}", adapter.get_info());
+        let (device, queue) = Self::request_device(&adapter, config).await?;
+        let limits = device.limits();
+        log_debug!("Device limits: {limits:?}");
+        let lost = Arc::new(AtomicBool::new(false));
+        let lost_flag = lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            // wgpu also calls this when the device is dropped, which isn't a loss.
+            if let wgpu::DeviceLostReason::Dropped | wgpu::DeviceLostReason::ReplacedCallback =
+                reason
+            {
+                return;
+            }
+            log_warn!("The device was lost ({reason:?}): {message}");
+            lost_flag.store(true, Ordering::Relaxed);
+        });
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            limits,
+            config: config.clone(),
+            lost,
+            bind_group_layouts: Mutex::default(),
+        })
+    }
+
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// The limits the device actually got, at least `StateConfig::required_limits`.
+    pub fn limits(&self) -> &wgpu::Limits {
+        &self.limits
+    }
+
+    /// The power preference the adapter was found with, which differs from
+    /// `StateConfig::power_preference` if there was no adapter for that.
+    pub fn power_preference(&self) -> PowerPreference {
+        self.config.power_preference
+    }
+
+    /// Whether the adapter had to be requested with `force_fallback_adapter`, usually a
+    /// software renderer.
+    pub fn is_fallback_adapter(&self) -> bool {
+        self.config.force_fallback_adapter
+    }
+
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// What the device may do with textures of `format`. Only the features every adapter
+    /// guarantees, unless the device has `Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`.
+    pub fn texture_format_features(
+        &self,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::TextureFormatFeatures {
+        let features = self.device.features();
+        if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            self.adapter.get_texture_format_features(format)
+        } else {
+            format.guaranteed_format_features(features)
+        }
+    }
+
+    /// The adapter and the most important device limits in a few lines, e.g. for bug reports.
+    pub fn summary(&self) -> String {
+        let info = self.adapter_info();
+        let limits = &self.limits;
+        format!(
+            "Adapter: {} ({:?}, {:?})\n\
+             Driver: {} {}\n\
+             Max texture size: {}\n\
+             Max bind groups: {}\n\
+             Max buffer size: {}\n\
+             Max uniform buffer binding size: {}\n\
+             Max storage buffer binding size: {}\n\
+             Max vertex buffers: {}, attributes: {}\n\
+             Max compute workgroup size: {}x{}x{}, invocations: {}",
+            info.name,
+            info.backend,
+            info.device_type,
+            info.driver,
+            info.driver_info,
+            limits.max_texture_dimension_2d,
+            limits.max_bind_groups,
+            limits.max_buffer_size,
+            limits.max_uniform_buffer_binding_size,
+            limits.max_storage_buffer_binding_size,
+            limits.max_vertex_buffers,
+            limits.max_vertex_attributes,
+            limits.max_compute_workgroup_size_x,
+            limits.max_compute_workgroup_size_y,
+            limits.max_compute_workgroup_size_z,
+            limits.max_compute_invocations_per_workgroup,
+        )
+    }
+
+    /// Whether the device was lost, e.g. because of a driver reset. Nothing created on it
+    /// works anymore, see `State::recreate_gpu`.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Returns the layout created for the same label and entries before, or creates it. Saves
+    /// creating the same layout for every pipeline that uses it.
+    pub fn get_or_create_bind_group_layout(
+        &self,
+        descriptor: &wgpu::BindGroupLayoutDescriptor,
+    ) -> Arc<wgpu::BindGroupLayout> {
+        let mut layouts = self.bind_group_layouts.lock().unwrap();
+        layouts
+            .entry((
+                descriptor.label.map(str::to_owned),
+                descriptor.entries.to_vec(),
+            ))
+            .or_insert_with(|| Arc::new(self.device.create_bind_group_layout(descriptor)))
+            .clone()
+    }
+
+    pub(crate) fn create_instance(config: &StateConfig) -> Instance {
+        // WGPU STUFF
+        Instance::new(InstanceDescriptor {
+            backends: config.effective_backends(),
+            ..Default::default()
+        })
+    }
+
+    /// Requests an adapter compatible with `surface`, any adapter without a surface.
+    /// If there is none with the options of `config`, e.g. on laptops with only an integrated
+    /// GPU or in VMs, the other power preference and then the fallback adapter are tried.
+    /// `config` is updated to the options that found the adapter.
+    pub(crate) async fn request_adapter(
+        instance: &Instance,
+        config: &mut StateConfig,
+        surface: Option<&Surface<'_>>,
+    ) -> Result<Adapter, StateError> {
+        let requested = (config.power_preference, config.force_fallback_adapter);
+        let other_power_preference = match config.power_preference {
+            PowerPreference::HighPerformance => PowerPreference::LowPower,
+            PowerPreference::LowPower | PowerPreference::None => PowerPreference::HighPerformance,
+        };
+        let attempts = [
+            requested,
+            (other_power_preference, config.force_fallback_adapter),
+            (config.power_preference, true),
+        ];
+        for (power_preference, force_fallback_adapter) in attempts {
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference,
+                    compatible_surface: surface,
+                    force_fallback_adapter,
+                })
+                .await;
+            if let Some(adapter) = adapter {
+                if (power_preference, force_fallback_adapter) != requested {
+                    log_warn!(
+                        "No adapter for {:?}, using {power_preference:?} with \
+                         force_fallback_adapter {force_fallback_adapter}",
+                        requested.0,
+                    );
+                }
+                config.power_preference = power_preference;
+                config.force_fallback_adapter = force_fallback_adapter;
+                return Ok(adapter);
+            }
+        }
+        Err(StateError::NoAdapter)
+    }
+
+    async fn request_device(
+        adapter: &Adapter,
+        config: &StateConfig,
+    ) -> Result<(Device, Queue), StateError> {
+        // Without timestamp queries frames just aren't timed.
+        let optional_features = config.optional_features
+            | if cfg!(feature = "gpu-timing") {
+                wgpu::Features::TIMESTAMP_QUERY
+            } else {
+                wgpu::Features::empty()
+            };
+        let required_features = config.required_features | (adapter.features() & optional_features);
+        let device_and_queue = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features,
+                    required_limits: config.required_limits.clone(),
+                    label: config.device_label.as_deref(),
+                    memory_hints: config.memory_hints.clone(),
+                },
+                config.trace_path.as_deref(),
+            )
+            .await?;
+        Ok(device_and_queue)
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The number of frames whose timestamps can be in flight at once.
+const SLOT_COUNT: usize = 3;
+/// A timestamp at the beginning of the first pass and one at the end of the last.
+const QUERY_COUNT: u32 = 2;
+const QUERY_BUFFER_SIZE: u64 = QUERY_COUNT as u64 * wgpu::QUERY_SIZE as u64;
+
+/// A readback buffer for the timestamps of one frame.
+struct Slot {
+    buffer: wgpu::Buffer,
+    /// Whether the timestamps were copied into the buffer and not read back yet.
+    in_flight: bool,
+    /// Set by the `map_async` callback.
+    mapped: Arc<AtomicBool>,
+}
+
+/// Measures how long the GPU takes for the render passes of a frame with timestamp queries.
+/// The timestamps are read back without blocking, so the measured frame is a few frames old.
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    slots: Vec<Slot>,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    last_frame_time: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// Returns `None` if the device wasn't created with `Features::TIMESTAMP_QUERY`.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Timestamp Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size: QUERY_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let slots = (0..SLOT_COUNT)
+            .map(|_| Slot {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Timestamp Readback Buffer"),
+                    size: QUERY_BUFFER_SIZE,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                in_flight: false,
+                mapped: Arc::new(AtomicBool::new(false)),
+            })
+            .collect();
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            slots,
+            period: queue.get_timestamp_period(),
+            last_frame_time: None,
+        })
+    }
+
+    pub fn last_frame_time(&self) -> Option<Duration> {
+        self.last_frame_time
+    }
+
+    /// The timestamp writes for a pass, `first` and `last` say where the pass is in the frame.
+    pub fn timestamp_writes(&self, first: bool, last: bool) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: first.then_some(0),
+            end_of_pass_write_index: last.then_some(1),
+        }
+    }
+
+    /// Reads back the timestamps of finished frames.
+    pub fn collect(&mut self, device: &wgpu::Device) {
+        // Native backends only call the map callbacks while the device is polled.
+        device.poll(wgpu::Maintain::Poll);
+        for slot in &mut self.slots {
+            if !slot.in_flight || !slot.mapped.load(Ordering::Acquire) {
+                continue;
+            }
+            {
+                let data = slot.buffer.slice(..).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                let ticks = timestamps[1].saturating_sub(timestamps[0]);
+                self.last_frame_time = Some(Duration::from_nanos(
+                    (ticks as f64 * self.period as f64) as u64,
+                ));
+            }
+            slot.buffer.unmap();
+            slot.in_flight = false;
+            slot.mapped.store(false, Ordering::Release);
+        }
+    }
+
+    /// Resolves the timestamps of this frame into a free readback buffer and returns its
+    /// index for `map`, `None` if all buffers are still in flight and the frame is skipped.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) -> Option<usize> {
+        let index = self.slots.iter().position(|slot| !slot.in_flight)?;
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.slots[index].buffer,
+            0,
+            QUERY_BUFFER_SIZE,
+        );
+        self.slots[index].in_flight = true;
+        Some(index)
+    }
+
+    /// Starts mapping the buffer returned by `resolve`, call it after the frame was submitted.
+    pub fn map(&self, index: usize) {
+        let mapped = self.slots[index].mapped.clone();
+        self.slots[index]
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if result.is_ok() {
+                    mapped.store(true, Ordering::Release);
+                }
+            });
+    }
+}
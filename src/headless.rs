@@ -0,0 +1,128 @@
+#[cfg(not(target_arch = "wasm32"))]
+use pollster::FutureExt;
+use std::sync::Arc;
+
+use wgpu::SurfaceConfiguration;
+
+use crate::{GpuContext, State, StateConfig, StateError};
+
+/// The offscreen texture used instead of a surface in headless mode.
+pub(crate) struct HeadlessTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl HeadlessTarget {
+    pub fn new(device: &wgpu::Device, config: &SurfaceConfiguration) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Texture"),
+            size: wgpu::Extent3d {
+                width: config.width.max(1),
+                height: config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &config.view_formats,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+}
+
+impl State {
+    /// Creates a state without a window that renders into an offscreen texture,
+    /// e.g. for CI or server-side rendering. Use `render_to_texture` instead of `render`. The
+    /// size is clamped to the maximum texture size of the device.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_headless(
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self, StateError> {
+        Self::new_headless_async(width, height, format, StateConfig::default()).block_on()
+    }
+
+    /// Async version of `State::new_headless` that also takes a config.
+    pub async fn new_headless_async(
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        config: StateConfig,
+    ) -> Result<Self, StateError> {
+        let gpu = GpuContext::new_async(config).await?;
+        let size = Self::clamp_to_limits(&gpu, winit::dpi::PhysicalSize::new(width, height));
+        // Checked like the surface usage of a window, creating the texture would panic.
+        let usage = gpu.config.surface_usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if !gpu
+            .texture_format_features(format)
+            .allowed_usages
+            .contains(usage)
+        {
+            return Err(StateError::UnsupportedSurfaceUsage(usage));
+        }
+        // Only the format, size and usage matter without a surface.
+        let surface_config = SurfaceConfiguration {
+            usage,
+            format,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let mut state = Self::from_parts(Arc::new(gpu), None, surface_config, None);
+        state.set_view_formats(state.gpu.config.view_formats.clone());
+        if state.gpu.config.sample_count != 1 {
+            state.set_sample_count(state.gpu.config.sample_count);
+        }
+        Ok(state)
+    }
+
+    /// Recreates the offscreen texture after its size or format changed.
+    pub(crate) fn recreate_headless_target(&mut self) {
+        if self.headless_target.is_some() {
+            self.headless_target =
+                Some(HeadlessTarget::new(&self.gpu.device, &self.surface_config));
+        }
+    }
+
+    /// Renders a frame into the offscreen texture and returns its view, `None` if the state
+    /// has a window.
+    pub fn render_to_texture(&mut self) -> Option<&wgpu::TextureView> {
+        // Checked up front, so nothing is uploaded when there is no target.
+        self.headless_target.as_ref()?;
+        let label = self.frame_label("Headless Encoder");
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: label.as_deref(),
+            });
+        self.upload_frame_data(&mut encoder);
+        let mut render_fn = std::mem::replace(&mut self.render_fn, Box::new(|_| {}));
+        let mut render_graph = std::mem::take(&mut self.render_graph);
+        if let Some(target) = &self.headless_target {
+            self.run_render_fn(
+                &mut render_fn,
+                &mut render_graph,
+                &mut encoder,
+                self.frame_target(&target.texture, &target.view),
+            );
+        }
+        self.render_fn = render_fn;
+        self.render_graph = render_graph;
+        self.submit_frame(encoder);
+        self.headless_target.as_ref().map(|target| &target.view)
+    }
+
+    /// The offscreen texture of headless mode. It can be copied from or sampled if
+    /// `StateConfig::surface_usage` has `COPY_SRC` or `TEXTURE_BINDING`.
+    pub fn headless_texture(&self) -> Option<&wgpu::Texture> {
+        self.headless_target.as_ref().map(|target| &target.texture)
+    }
+}
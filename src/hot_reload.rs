@@ -0,0 +1,139 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use pollster::FutureExt;
+
+use crate::{
+    logging::{log_info, log_warn},
+    mesh, State,
+};
+
+/// How often the watched file's modification time is checked.
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The shader file of `State::watch_shader`.
+pub(crate) struct ShaderWatch {
+    path: PathBuf,
+    /// Of the file when it was last read, `None` before the first read.
+    modified: Option<SystemTime>,
+    last_check: Instant,
+    /// The last source that compiled, reused when the pipelines are recreated.
+    source: Option<String>,
+    error: Option<String>,
+}
+
+impl ShaderWatch {
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// When the file is checked next.
+    pub fn next_check(&self) -> Instant {
+        self.last_check + CHECK_INTERVAL
+    }
+}
+
+impl State {
+    /// Draws the mesh with the WGSL shader at `path` instead of the built-in one and
+    /// recompiles it whenever the file changes, without recreating the window or device. The
+    /// shader needs the inputs and bindings of `shaders/mesh.wgsl`, which is a good start.
+    /// If it doesn't compile the previous pipeline is kept and the error is printed, see
+    /// `shader_error`. The file's modification time is checked a few times per second.
+    pub fn watch_shader(&mut self, path: PathBuf) {
+        self.shader_watch = Some(ShaderWatch {
+            path,
+            modified: None,
+            last_check: Instant::now(),
+            source: None,
+            error: None,
+        });
+        self.reload_watched_shader();
+    }
+
+    /// Goes back to the built-in mesh shader.
+    pub fn unwatch_shader(&mut self) {
+        if self.shader_watch.take().is_some() {
+            self.recreate_pipelines();
+            self.request_redraw();
+        }
+    }
+
+    /// Why the watched shader last failed to load or compile, `None` once it compiles again.
+    pub fn shader_error(&self) -> Option<&str> {
+        self.shader_watch.as_ref()?.error.as_deref()
+    }
+
+    /// Reloads the watched shader if the file changed, called in `about_to_wait`.
+    pub(crate) fn check_watched_shader(&mut self) {
+        let Some(watch) = &mut self.shader_watch else {
+            return;
+        };
+        if watch.last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        watch.last_check = Instant::now();
+        let modified = std::fs::metadata(&watch.path).and_then(|metadata| metadata.modified());
+        if modified.ok() != watch.modified {
+            self.reload_watched_shader();
+        }
+    }
+
+    fn reload_watched_shader(&mut self) {
+        let Some(watch) = &mut self.shader_watch else {
+            return;
+        };
+        watch.modified = std::fs::metadata(&watch.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let path = watch.path.clone();
+        let result = std::fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))
+            .and_then(|source| {
+                self.compile_mesh_shader(&path, &source)
+                    .map(|pipeline| (source, pipeline))
+            });
+        let Some(watch) = &mut self.shader_watch else {
+            return;
+        };
+        match result {
+            Ok((source, pipeline)) => {
+                log_info!("Reloaded {}", path.display());
+                watch.source = Some(source);
+                watch.error = None;
+                self.mesh_pipeline = pipeline;
+                self.request_redraw();
+            }
+            Err(err) => {
+                log_warn!("{err}");
+                watch.error = Some(err);
+            }
+        }
+    }
+
+    /// Compiles `source` into a mesh pipeline, catching the validation errors wgpu would
+    /// otherwise panic with.
+    fn compile_mesh_shader(
+        &self,
+        path: &Path,
+        source: &str,
+    ) -> Result<wgpu::RenderPipeline, String> {
+        let device = &self.gpu.device;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: path.to_str(),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline = mesh::create_pipeline_with_shader(
+            device,
+            &shader,
+            &self.camera.bind_group_layout,
+            self.target_formats(),
+        );
+        match device.pop_error_scope().block_on() {
+            Some(err) => Err(format!("Failed to compile {}: {err}", path.display())),
+            None => Ok(pipeline),
+        }
+    }
+}
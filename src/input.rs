@@ -0,0 +1,449 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+// std's Instant panics in the browser.
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, Ime, MouseButton, MouseScrollDelta, Touch, TouchPhase, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+/// Scroll distance accumulated since the last `InputState::take_scroll_delta`.
+/// Mouse wheels usually report lines, touchpads pixels.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScrollDelta {
+    pub lines: (f32, f32),
+    pub pixels: (f64, f64),
+}
+
+/// A finger on the touchscreen, see `InputState::active_touches`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    /// Unique while the finger is down, the next touch may get the same id again.
+    pub id: u64,
+    /// `Started` or `Moved`, ended touches are removed.
+    pub phase: TouchPhase,
+    pub location: PhysicalPosition<f64>,
+}
+
+/// A press that may become the first half of a double-click.
+#[derive(Debug, Clone, Copy)]
+struct Click {
+    time: Instant,
+    position: Option<PhysicalPosition<f64>>,
+}
+
+/// How close in time and space two presses have to be to count as a double-click.
+#[derive(Debug, Clone, Copy)]
+struct DoubleClickThreshold {
+    time: Duration,
+    /// In physical pixels.
+    distance: f64,
+}
+
+impl Default for DoubleClickThreshold {
+    fn default() -> Self {
+        // Windows' defaults, most platforms use similar ones.
+        Self {
+            time: Duration::from_millis(500),
+            distance: 4.0,
+        }
+    }
+}
+
+/// Keeps track of the input state of a window, fed by `State::window_event`.
+#[derive(Debug, Default)]
+pub struct InputState {
+    pressed_keys: HashSet<KeyCode>,
+    just_pressed_keys: HashSet<KeyCode>,
+    just_released_keys: HashSet<KeyCode>,
+    /// `None` while the cursor is outside of the window.
+    cursor_position: Option<PhysicalPosition<f64>>,
+    pressed_mouse_buttons: HashSet<MouseButton>,
+    /// The last press of every button that can still become a double-click.
+    last_clicks: HashMap<MouseButton, Click>,
+    just_double_clicked: HashSet<MouseButton>,
+    double_click_threshold: DoubleClickThreshold,
+    scroll_delta: ScrollDelta,
+    touches: HashMap<u64, TouchPoint>,
+    /// Whether a single finger moves the cursor and presses the left mouse button.
+    touch_as_cursor: bool,
+    /// Text typed since the last `take_text_input`, committed by the IME or from key presses.
+    text_input: String,
+    /// The text the IME is composing and the cursor or selection in it as byte offsets.
+    preedit: Option<(String, Option<(usize, usize)>)>,
+    /// While the IME is enabled it commits the text, key presses don't add any.
+    ime_enabled: bool,
+}
+
+impl InputState {
+    /// Whether the physical key is currently held down.
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    /// Whether the physical key went down since the last `end_frame`, auto-repeat doesn't count.
+    pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+
+    /// Whether the physical key was released since the last `end_frame`. Keys held while the
+    /// window loses focus don't count, their releases aren't reported.
+    pub fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.just_released_keys.contains(&key)
+    }
+
+    /// The cursor position in physical pixels, `None` while the cursor is outside of the window.
+    pub fn cursor_position(&self) -> Option<PhysicalPosition<f64>> {
+        self.cursor_position
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_mouse_buttons.contains(&button)
+    }
+
+    /// Whether the button was pressed twice in quick succession at about the same position
+    /// since the last `end_frame`. A third press starts the next double-click.
+    pub fn just_double_clicked(&self, button: MouseButton) -> bool {
+        self.just_double_clicked.contains(&button)
+    }
+
+    /// How much time may pass between the presses of a double-click and how far apart in
+    /// physical pixels they may be. 500 ms and 4 pixels by default.
+    pub fn set_double_click_threshold(&mut self, time: Duration, distance: f64) {
+        self.double_click_threshold = DoubleClickThreshold { time, distance };
+    }
+
+    /// Returns the scroll distance since the last call and resets it.
+    pub fn take_scroll_delta(&mut self) -> ScrollDelta {
+        std::mem::take(&mut self.scroll_delta)
+    }
+
+    /// Returns the text typed since the last call and clears it, e.g. for a text field.
+    /// Needs `State::set_ime_allowed(true)` to get composed text, e.g. Chinese or Japanese.
+    pub fn take_text_input(&mut self) -> String {
+        std::mem::take(&mut self.text_input)
+    }
+
+    /// The text the IME is composing but didn't commit yet, to show it differently, e.g.
+    /// underlined. The range is the cursor or selection in it as byte offsets, `None` hides
+    /// the cursor.
+    pub fn preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        self.preedit
+            .as_ref()
+            .map(|(text, cursor)| (text.as_str(), *cursor))
+    }
+
+    /// The fingers that are currently on the touchscreen, in no particular order.
+    pub fn active_touches(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.touches.values()
+    }
+
+    /// Lets a single finger act as the mouse, so apps written for the mouse work on
+    /// touchscreens. Off by default.
+    pub fn set_touch_as_cursor(&mut self, touch_as_cursor: bool) {
+        self.touch_as_cursor = touch_as_cursor;
+    }
+
+    fn handle_touch(&mut self, touch: &Touch) {
+        match touch.phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+                self.touches.insert(
+                    touch.id,
+                    TouchPoint {
+                        id: touch.id,
+                        phase: touch.phase,
+                        location: touch.location,
+                    },
+                );
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+            }
+        }
+        // Pinches and other gestures with more fingers don't move the cursor.
+        if !self.touch_as_cursor || self.touches.len() > 1 {
+            return;
+        }
+        match touch.phase {
+            TouchPhase::Started => {
+                self.cursor_position = Some(touch.location);
+                self.pressed_mouse_buttons.insert(MouseButton::Left);
+            }
+            TouchPhase::Moved => self.cursor_position = Some(touch.location),
+            // A cancelled touch wasn't meant as a click, but the button is released either way.
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.pressed_mouse_buttons.remove(&MouseButton::Left);
+            }
+        }
+    }
+
+    pub(crate) fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let (ElementState::Pressed, false, Some(text)) =
+                    (event.state, self.ime_enabled, &event.text)
+                {
+                    // Control characters like backspace and enter are keys, not text.
+                    self.text_input
+                        .extend(text.chars().filter(|c| !c.is_control()));
+                }
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    self.handle_key(key, event.state, event.repeat);
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some(*position);
+                // Moving away in between breaks the double-click, even when coming back.
+                let distance = self.double_click_threshold.distance;
+                self.last_clicks.retain(|_, click| {
+                    click.position.is_some_and(|click_position| {
+                        distance_between(click_position, *position) <= distance
+                    })
+                });
+            }
+            // The position is only known again after the next `CursorMoved`.
+            WindowEvent::CursorEntered { .. } | WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    self.pressed_mouse_buttons.insert(*button);
+                    self.handle_click(*button, Instant::now());
+                }
+                ElementState::Released => {
+                    self.pressed_mouse_buttons.remove(button);
+                }
+            },
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => {
+                    self.scroll_delta.lines.0 += x;
+                    self.scroll_delta.lines.1 += y;
+                }
+                MouseScrollDelta::PixelDelta(position) => {
+                    self.scroll_delta.pixels.0 += position.x;
+                    self.scroll_delta.pixels.1 += position.y;
+                }
+            },
+            WindowEvent::Touch(touch) => self.handle_touch(touch),
+            WindowEvent::Ime(ime) => match ime {
+                Ime::Enabled => self.ime_enabled = true,
+                // winit sends an empty preedit to clear it.
+                Ime::Preedit(text, _) if text.is_empty() => self.preedit = None,
+                Ime::Preedit(text, cursor) => self.preedit = Some((text.clone(), *cursor)),
+                Ime::Commit(text) => self.text_input.push_str(text),
+                Ime::Disabled => {
+                    self.ime_enabled = false;
+                    self.preedit = None;
+                }
+            },
+            // Releases aren't reported while the window is unfocused, so forget everything held.
+            WindowEvent::Focused(false) => {
+                self.pressed_keys.clear();
+                self.pressed_mouse_buttons.clear();
+                self.last_clicks.clear();
+                self.touches.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /// Resets the per-frame state, called in `about_to_wait`.
+    pub(crate) fn end_frame(&mut self) {
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
+        self.just_double_clicked.clear();
+    }
+
+    fn handle_key(&mut self, key: KeyCode, state: ElementState, repeat: bool) {
+        match state {
+            ElementState::Pressed => {
+                // Auto-repeat sends more presses for a key that is already held.
+                if !repeat && self.pressed_keys.insert(key) {
+                    self.just_pressed_keys.insert(key);
+                }
+            }
+            ElementState::Released => {
+                if self.pressed_keys.remove(&key) {
+                    self.just_released_keys.insert(key);
+                }
+            }
+        }
+    }
+
+    /// Handles a press of `button` at `now`.
+    fn handle_click(&mut self, button: MouseButton, now: Instant) {
+        let threshold = self.double_click_threshold;
+        let is_double_click = self.last_clicks.remove(&button).is_some_and(|click| {
+            let close = match (click.position, self.cursor_position) {
+                (Some(first), Some(second)) => {
+                    distance_between(first, second) <= threshold.distance
+                }
+                // Without a cursor position, e.g. on some touchscreens, only the time counts.
+                _ => true,
+            };
+            now - click.time <= threshold.time && close
+        });
+        if is_double_click {
+            self.just_double_clicked.insert(button);
+        } else {
+            self.last_clicks.insert(
+                button,
+                Click {
+                    time: now,
+                    position: self.cursor_position,
+                },
+            );
+        }
+    }
+}
+
+fn distance_between(a: PhysicalPosition<f64>, b: PhysicalPosition<f64>) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::DeviceId;
+
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn cursor_moved(x: f64, y: f64) -> WindowEvent {
+        WindowEvent::CursorMoved {
+            device_id: DeviceId::dummy(),
+            position: PhysicalPosition::new(x, y),
+        }
+    }
+
+    #[test]
+    fn key_transitions_last_until_the_end_of_the_frame() {
+        let mut input = InputState::default();
+        input.handle_key(KeyCode::KeyW, ElementState::Pressed, false);
+        assert!(input.is_key_pressed(KeyCode::KeyW));
+        assert!(input.is_key_just_pressed(KeyCode::KeyW));
+        assert!(!input.is_key_just_released(KeyCode::KeyW));
+
+        input.end_frame();
+        assert!(input.is_key_pressed(KeyCode::KeyW));
+        assert!(!input.is_key_just_pressed(KeyCode::KeyW));
+
+        input.handle_key(KeyCode::KeyW, ElementState::Released, false);
+        assert!(!input.is_key_pressed(KeyCode::KeyW));
+        assert!(input.is_key_just_released(KeyCode::KeyW));
+
+        input.end_frame();
+        assert!(!input.is_key_just_released(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn pressing_and_releasing_in_one_frame_is_both_just_pressed_and_released() {
+        let mut input = InputState::default();
+        input.handle_key(KeyCode::Space, ElementState::Pressed, false);
+        input.handle_key(KeyCode::Space, ElementState::Released, false);
+        assert!(!input.is_key_pressed(KeyCode::Space));
+        assert!(input.is_key_just_pressed(KeyCode::Space));
+        assert!(input.is_key_just_released(KeyCode::Space));
+    }
+
+    #[test]
+    fn auto_repeat_is_not_a_new_press() {
+        let mut input = InputState::default();
+        input.handle_key(KeyCode::KeyA, ElementState::Pressed, false);
+        input.end_frame();
+        input.handle_key(KeyCode::KeyA, ElementState::Pressed, true);
+        assert!(input.is_key_pressed(KeyCode::KeyA));
+        assert!(!input.is_key_just_pressed(KeyCode::KeyA));
+        // A repeat without the first press, e.g. when the window got focus while the key was
+        // held, doesn't press it either.
+        input.handle_key(KeyCode::KeyB, ElementState::Pressed, true);
+        assert!(!input.is_key_pressed(KeyCode::KeyB));
+    }
+
+    #[test]
+    fn releasing_a_key_that_was_not_pressed_is_ignored() {
+        let mut input = InputState::default();
+        input.handle_key(KeyCode::KeyQ, ElementState::Released, false);
+        assert!(!input.is_key_just_released(KeyCode::KeyQ));
+    }
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut input = InputState::default();
+        input.handle_key(KeyCode::ShiftLeft, ElementState::Pressed, false);
+        input.pressed_mouse_buttons.insert(MouseButton::Left);
+        input.handle_click(MouseButton::Left, Instant::now());
+        input.handle_window_event(&WindowEvent::Focused(false));
+        assert!(!input.is_key_pressed(KeyCode::ShiftLeft));
+        assert!(!input.is_key_just_released(KeyCode::ShiftLeft));
+        assert!(!input.is_mouse_button_pressed(MouseButton::Left));
+        // The press before losing focus doesn't start a double-click.
+        input.handle_click(MouseButton::Left, Instant::now());
+        assert!(!input.just_double_clicked(MouseButton::Left));
+    }
+
+    #[test]
+    fn two_quick_presses_are_a_double_click() {
+        let mut input = InputState::default();
+        let start = Instant::now();
+        input.handle_window_event(&cursor_moved(10.0, 10.0));
+        input.handle_click(MouseButton::Left, start);
+        assert!(!input.just_double_clicked(MouseButton::Left));
+        input.handle_click(MouseButton::Left, start + 300 * MS);
+        assert!(input.just_double_clicked(MouseButton::Left));
+        input.end_frame();
+        assert!(!input.just_double_clicked(MouseButton::Left));
+        // The third press starts the next double-click.
+        input.handle_click(MouseButton::Left, start + 400 * MS);
+        assert!(!input.just_double_clicked(MouseButton::Left));
+    }
+
+    #[test]
+    fn slow_presses_are_no_double_click() {
+        let mut input = InputState::default();
+        let start = Instant::now();
+        input.handle_click(MouseButton::Left, start);
+        input.handle_click(MouseButton::Left, start + 501 * MS);
+        assert!(!input.just_double_clicked(MouseButton::Left));
+        // The slow press starts a new one.
+        input.handle_click(MouseButton::Left, start + 700 * MS);
+        assert!(input.just_double_clicked(MouseButton::Left));
+    }
+
+    #[test]
+    fn moving_away_between_presses_breaks_the_double_click() {
+        let mut input = InputState::default();
+        input.set_double_click_threshold(500 * MS, 4.0);
+        let start = Instant::now();
+        input.handle_window_event(&cursor_moved(10.0, 10.0));
+        input.handle_click(MouseButton::Left, start);
+        input.handle_window_event(&cursor_moved(20.0, 10.0));
+        input.handle_window_event(&cursor_moved(10.0, 10.0));
+        input.handle_click(MouseButton::Left, start + 100 * MS);
+        assert!(!input.just_double_clicked(MouseButton::Left));
+
+        input.handle_window_event(&cursor_moved(12.0, 12.0));
+        input.handle_click(MouseButton::Left, start + 200 * MS);
+        assert!(input.just_double_clicked(MouseButton::Left));
+    }
+
+    #[test]
+    fn buttons_double_click_separately() {
+        let mut input = InputState::default();
+        let start = Instant::now();
+        input.handle_click(MouseButton::Left, start);
+        input.handle_click(MouseButton::Right, start + 100 * MS);
+        assert!(!input.just_double_clicked(MouseButton::Right));
+        input.handle_click(MouseButton::Left, start + 200 * MS);
+        assert!(input.just_double_clicked(MouseButton::Left));
+        assert!(!input.just_double_clicked(MouseButton::Right));
+    }
+}
@@ -0,0 +1,90 @@
+/// Per-instance data of the mesh as it is laid out in the instance buffer, see
+/// `State::set_instances`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstanceRaw {
+    /// Column-major model matrix, applied before the camera.
+    pub model: [[f32; 4]; 4],
+}
+
+// SAFETY: `InstanceRaw` is `repr(C)`, only contains `f32`s and has no padding.
+unsafe impl bytemuck::Zeroable for InstanceRaw {}
+unsafe impl bytemuck::Pod for InstanceRaw {}
+
+impl InstanceRaw {
+    pub const IDENTITY: Self = Self {
+        model: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    // The locations follow the ones of `Vertex`, with room for more vertex attributes.
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+    ];
+
+    /// Matches the `InstanceInput` of `shaders/mesh.wgsl`, one column per location.
+    pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &Self::ATTRIBUTES,
+    };
+}
+
+impl Default for InstanceRaw {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// The instance buffer, it only grows so uploading fewer instances doesn't reallocate.
+pub(crate) struct Instances {
+    pub buffer: wgpu::Buffer,
+    /// How many instances fit into `buffer`.
+    capacity: usize,
+    pub count: u32,
+}
+
+impl Instances {
+    /// Starts with a single identity instance, so the mesh is drawn once as it is.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let mut instances = Self {
+            buffer: Self::create_buffer(device, 1),
+            capacity: 1,
+            count: 0,
+        };
+        instances.upload(device, queue, &[InstanceRaw::IDENTITY]);
+        instances
+    }
+
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &[InstanceRaw],
+    ) {
+        if instances.len() > self.capacity {
+            // Grow to the next power of two, so adding instances one by one doesn't
+            // reallocate every time.
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        self.count = instances.len() as u32;
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{pipeline::TargetFormats, State};
+
+/// Creates the pipeline filling the bars, it draws the blend constant everywhere and leaves
+/// the depth buffer alone.
+pub(crate) fn create_pipeline(
+    device: &wgpu::Device,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/letterbox.wgsl"));
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Letterbox Pipeline Layout"),
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+    // The shader writes 1, so the blend constant ends up in the target.
+    let constant = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Constant,
+        dst_factor: wgpu::BlendFactor::Zero,
+        operation: wgpu::BlendOperation::Add,
+    };
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Letterbox Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: targets.color,
+                blend: Some(wgpu::BlendState {
+                    color: constant,
+                    alpha: constant,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: targets.depth,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: targets.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// The largest rectangle with `aspect` centered in a `width` by `height` frame, as `x`, `y`,
+/// width and height in pixels. A zero-size frame counts as 1 by 1 pixels, like the surface
+/// textures while minimized.
+fn fit_aspect(width: u32, height: u32, aspect: f32) -> (u32, u32, u32, u32) {
+    let (width, height) = (width.max(1), height.max(1));
+    if width as f32 / height as f32 > aspect {
+        // Wider than the content, bars left and right.
+        let fitted = ((height as f32 * aspect).round() as u32).clamp(1, width);
+        ((width - fitted) / 2, 0, fitted, height)
+    } else {
+        // Taller than the content, bars at the top and bottom.
+        let fitted = ((width as f32 / aspect).round() as u32).clamp(1, height);
+        (0, (height - fitted) / 2, width, fitted)
+    }
+}
+
+impl State {
+    pub fn target_aspect(&self) -> Option<f32> {
+        self.target_aspect
+    }
+
+    /// Keeps the scene at `aspect` (width / height), e.g. 4.0 / 3.0 for a retro game, by
+    /// drawing it into the largest centered viewport with that aspect ratio and filling the
+    /// rest with bars, see `set_letterbox_color`. `None` fills the whole frame.
+    /// Non-positive or non-finite ratios count as `None`.
+    pub fn set_target_aspect(&mut self, aspect: Option<f32>) {
+        self.target_aspect = aspect.filter(|aspect| aspect.is_finite() && *aspect > 0.0);
+        self.letterbox_pipeline = self
+            .target_aspect
+            .map(|_| create_pipeline(&self.gpu.device, self.target_formats()));
+        self.request_redraw();
+    }
+
+    /// The color of the bars around the scene, black by default.
+    pub fn set_letterbox_color(&mut self, color: wgpu::Color) {
+        self.letterbox_color = color;
+        self.request_redraw();
+    }
+
+    /// The viewport the scene is drawn into as `x`, `y`, width and height in pixels, `None`
+    /// without a target aspect ratio. Own passes can use it with `RenderPass::set_viewport`
+    /// to line up with the scene.
+    pub fn letterbox_viewport(&self) -> Option<(u32, u32, u32, u32)> {
+        Some(fit_aspect(
+            self.surface_config.width,
+            self.surface_config.height,
+            self.target_aspect?,
+        ))
+    }
+
+    /// Restricts the draws that follow to the letterbox viewport.
+    pub(crate) fn set_letterbox_viewport(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some((x, y, width, height)) = self.letterbox_viewport() {
+            render_pass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+        }
+    }
+
+    /// Fills the bars around the letterbox viewport.
+    pub(crate) fn draw_letterbox_bars(&self, render_pass: &mut wgpu::RenderPass) {
+        let (Some(pipeline), Some((x, y, width, height))) =
+            (&self.letterbox_pipeline, self.letterbox_viewport())
+        else {
+            return;
+        };
+        let frame_width = self.surface_config.width.max(1);
+        let frame_height = self.surface_config.height.max(1);
+        let bars = [
+            (0, 0, x, frame_height),
+            (x + width, 0, frame_width - x - width, frame_height),
+            (0, 0, frame_width, y),
+            (0, y + height, frame_width, frame_height - y - height),
+        ];
+        render_pass.set_viewport(0.0, 0.0, frame_width as f32, frame_height as f32, 0.0, 1.0);
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_blend_constant(self.surface_color(self.letterbox_color));
+        for (x, y, width, height) in bars {
+            if width > 0 && height > 0 {
+                render_pass.set_scissor_rect(x, y, width, height);
+                render_pass.draw(0..3, 0..1);
+            }
+        }
+        render_pass.set_scissor_rect(0, 0, frame_width, frame_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wider_frames_get_bars_left_and_right() {
+        assert_eq!(fit_aspect(1920, 1080, 4.0 / 3.0), (240, 0, 1440, 1080));
+        // An odd leftover puts the extra pixel into the right bar.
+        assert_eq!(fit_aspect(101, 50, 1.0), (25, 0, 50, 50));
+    }
+
+    #[test]
+    fn taller_frames_get_bars_at_the_top_and_bottom() {
+        // 1080 / (16 / 9) = 607.5 is rounded.
+        assert_eq!(fit_aspect(1080, 1920, 16.0 / 9.0), (0, 656, 1080, 608));
+        assert_eq!(fit_aspect(600, 800, 1.0), (0, 100, 600, 600));
+    }
+
+    #[test]
+    fn matching_frames_have_no_bars() {
+        assert_eq!(fit_aspect(800, 600, 4.0 / 3.0), (0, 0, 800, 600));
+        assert_eq!(fit_aspect(1920, 1080, 16.0 / 9.0), (0, 0, 1920, 1080));
+    }
+
+    #[test]
+    fn zero_size_frames_count_as_one_pixel() {
+        assert_eq!(fit_aspect(0, 0, 16.0 / 9.0), (0, 0, 1, 1));
+        assert_eq!(fit_aspect(0, 600, 4.0 / 3.0), (0, 299, 1, 1));
+        assert_eq!(fit_aspect(800, 0, 4.0 / 3.0), (399, 0, 1, 1));
+    }
+
+    #[test]
+    fn extreme_aspects_keep_at_least_one_pixel() {
+        assert_eq!(fit_aspect(100, 100, 0.0001), (49, 0, 1, 100));
+        assert_eq!(fit_aspect(100, 100, 10000.0), (0, 49, 100, 1));
+    }
+}
@@ -0,0 +1,2721 @@
+// MINIMAL WGPU AND WINIT USAGE EXAMPLE
+// Most code is taken from https://sotrh.github.io/learn-wgpu and the winit documentation.
+// I created this because setting up this boilerplate is annoying and got way more annoying with the new winit versions.
+
+//! Sets up a winit window with a wgpu surface and device, and renders into it with `State`.
+//! `run_default` opens a window and runs the event loop, `WinitWrapper` and `State` can also
+//! be used on their own.
+//!
+//! # Not included
+//!
+//! Integrations that need crates this crate doesn't depend on, not even behind a feature:
+//!
+//! - Debug UI with egui. `egui-wgpu` can draw into the frame from a render function, see
+//!   `State::set_render_fn`.
+//! - Gamepad input with gilrs, winit only reports keyboard, mouse and touch input. Its
+//!   events can be polled in `State::update`, `Gilrs::next_event` doesn't block.
+//! - Clipboard access with arboard. `arboard::Clipboard::new` can fail on headless systems,
+//!   so it's best created on the first copy or paste.
+//! - Image decoding with `image`. Textures, window icons and captured frames are 8 bit RGBA
+//!   pixels, so PNG or JPEG files have to be decoded first, and a `CapturedFrame`'s pixels
+//!   convert with `image::RgbaImage::from_raw`. Captures are saved as PNG without it.
+mod blend;
+mod camera;
+mod capture;
+mod color_target;
+#[cfg(feature = "compute")]
+mod compute;
+mod config;
+mod debug_lines;
+mod device_lost;
+mod error;
+mod frame_uniforms;
+mod fxaa;
+mod gpu;
+#[cfg(feature = "gpu-timing")]
+mod gpu_timing;
+mod headless;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+mod hot_reload;
+mod input;
+mod instance;
+mod letterbox;
+mod loader;
+mod logging;
+mod mesh;
+mod mipmap;
+mod monitor;
+mod occlusion;
+mod pipeline;
+mod placement;
+mod png;
+mod readback;
+mod render_context;
+mod render_graph;
+mod shadow;
+mod sprite;
+#[cfg(feature = "text")]
+mod text;
+mod texture;
+mod timing;
+#[cfg(feature = "triangle")]
+mod triangle;
+mod user_event;
+mod vertex_storage;
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+#[cfg(target_arch = "wasm32")]
+use std::{cell::RefCell, rc::Rc};
+// std's Instant panics in the browser.
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+use pollster::FutureExt;
+use wgpu::{Adapter, Device, Instance, Queue, Surface, SurfaceConfiguration};
+use winit::{
+    application::ApplicationHandler,
+    error::EventLoopError,
+    event::{DeviceEvent, DeviceId, StartCause, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    window::{
+        CursorGrabMode, CursorIcon, Fullscreen, Window, WindowAttributes, WindowId, WindowLevel,
+    },
+};
+
+pub use blend::BlendPreset;
+pub use camera::CameraUniform;
+pub use capture::CapturedFrame;
+pub use color_target::ColorTarget;
+#[cfg(feature = "compute")]
+pub use compute::ComputePass;
+pub use config::StateConfig;
+pub use error::{
+    CaptureError, Error, PipelineError, RenderGraphError, StateError, TextureError,
+    VertexStorageError,
+};
+pub use frame_uniforms::FrameUniforms;
+pub use gpu::GpuContext;
+pub use input::{InputState, ScrollDelta, TouchPoint};
+pub use instance::InstanceRaw;
+pub use mesh::Vertex;
+pub use placement::WindowPlacement;
+pub use render_context::RenderContext;
+pub use render_graph::RenderGraph;
+pub use shadow::{ShadowMap, ShadowPass};
+pub use sprite::Rect;
+pub use texture::{LoadedTexture, TextureOptions};
+pub use timing::{FixedTimestep, FrameTimer};
+pub use user_event::UserEvent;
+
+use logging::{log_debug, log_error, log_info, log_warn};
+use pipeline::TargetFormats;
+
+/// When `State` renders a new frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Renders frame after frame as fast as the present mode allows, e.g. for games.
+    #[default]
+    Continuous,
+    /// Only renders after a resize or a change through `State`, e.g. for tools, to save power.
+    /// Call `State::request_redraw` after changing things that `State` doesn't know about.
+    OnDemand,
+}
+
+/// How the surface colors are shown on the display, see `State::color_space`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Standard dynamic range, values above 1.0 are clipped.
+    Srgb,
+    /// HDR and wide gamut with linear values, 1.0 is SDR white and brighter colors go above
+    /// it. Used with the `Rgba16Float` surface format.
+    ExtendedLinearSrgb,
+}
+
+type FileDropCallback = Box<dyn FnMut(&Path)>;
+type DeviceLostCallback = Box<dyn FnMut(&mut State)>;
+type RenderFn = Box<dyn FnMut(&mut RenderContext)>;
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// wgpu switches the swapchain to the extended linear sRGB color space for this format.
+const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// The default size of the staging belt's buffers, see `State::set_staging_chunk_size`.
+const STAGING_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
+/// How often `State::render` tries to get the surface texture before skipping the frame.
+const SURFACE_TEXTURE_ATTEMPTS: u32 = 3;
+
+/// The main struct that holds the state of the application.
+/// Use this struct to hold the state of the application.
+pub struct State {
+    // WGPU STUFF
+    gpu: Arc<GpuContext>,
+    /// `None` in headless mode.
+    surface: Option<Surface<'static>>,
+    /// Also describes the offscreen target in headless mode.
+    surface_config: SurfaceConfiguration,
+    /// Queried once per surface, empty in headless mode.
+    surface_caps: wgpu::SurfaceCapabilities,
+    /// The texture rendered into in headless mode.
+    headless_target: Option<headless::HeadlessTarget>,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    /// The number of samples per pixel, 1 disables MSAA.
+    sample_count: u32,
+    /// As requested with `set_frame_latency`, the surface config has it clamped to the backend.
+    frame_latency: u32,
+    /// Multisampled color target that is resolved into the surface, `None` without MSAA.
+    msaa_view: Option<wgpu::TextureView>,
+    #[cfg(feature = "triangle")]
+    render_pipeline: wgpu::RenderPipeline,
+    mesh_pipeline: wgpu::RenderPipeline,
+    /// The shader file the mesh pipeline is compiled from, see `watch_shader`.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    shader_watch: Option<hot_reload::ShaderWatch>,
+    mesh: Option<mesh::Mesh>,
+    /// Drawn after the mesh, see `set_vertex_storage`.
+    vertex_storage: Option<vertex_storage::VertexStorage>,
+    /// Set while FXAA is on, see `set_fxaa`.
+    fxaa: Option<fxaa::Fxaa>,
+    /// The lines of `draw_line`, created with the first one.
+    debug_lines: Option<debug_lines::DebugLines>,
+    /// The sprites of `draw_sprite`, created with the first one.
+    sprite_batch: Option<sprite::SpriteBatch>,
+    /// The font of `draw_text`, see `set_font`.
+    #[cfg(feature = "text")]
+    text: Option<text::TextRenderer>,
+    instances: instance::Instances,
+    camera: camera::Camera,
+    /// Set by `update_camera`, uploaded at the start of the next frame.
+    pending_camera: Option<CameraUniform>,
+    frame_uniforms: frame_uniforms::FrameUniformBuffer,
+    /// When the state was created, `FrameUniforms::time` counts from here.
+    start_time: Instant,
+    /// Used for the uploads at the start of every frame, see `upload_frame_data`.
+    staging_belt: wgpu::util::StagingBelt,
+    staging_chunk_size: wgpu::BufferAddress,
+    /// Drawn into before the surface, see `add_color_target`.
+    color_targets: Vec<ColorTarget>,
+    /// `None` if the device doesn't support timestamp queries.
+    #[cfg(feature = "gpu-timing")]
+    gpu_timer: Option<gpu_timing::GpuTimer>,
+    /// `None` until `set_occlusion_query_count` is called.
+    occlusion_queries: Option<occlusion::OcclusionQueries>,
+
+    /// The color the surface is cleared with at the start of every frame.
+    clear_color: wgpu::Color,
+    /// How the main pass starts, `Clear` always holds `clear_color`.
+    load_op: wgpu::LoadOp<wgpu::Color>,
+    frame_timer: FrameTimer,
+    fixed_timestep: FixedTimestep,
+    /// Whether the FPS are shown in the window title.
+    fps_in_title: bool,
+    input: InputState,
+    /// Files dragged over the window that weren't dropped yet.
+    hovered_files: Vec<PathBuf>,
+    /// Dropped files since the last `take_dropped_files`.
+    dropped_files: Vec<PathBuf>,
+    file_drop_callback: Option<FileDropCallback>,
+    /// Called after the GPU resources were recreated, see `on_device_lost`.
+    device_lost_callback: Option<DeviceLostCallback>,
+    /// Records every frame, see `set_render_fn`.
+    render_fn: RenderFn,
+    /// The passes recorded around `render_fn`, see `add_pass`.
+    render_graph: RenderGraph,
+    /// The number of frames submitted so far, part of the debug labels.
+    frame_index: u64,
+    /// Put in front of the debug labels, see `set_label_prefix`.
+    label_prefix: Option<String>,
+    /// Runs the jobs of `spawn_load`.
+    #[cfg(not(target_arch = "wasm32"))]
+    loader: loader::Loader,
+    /// `UserEvent::Custom` messages since the last `take_custom_events`.
+    custom_events: Vec<Arc<dyn Any + Send + Sync>>,
+    fullscreen: bool,
+    /// From the last `Moved` event, see `window_position`.
+    window_position: Option<winit::dpi::PhysicalPosition<i32>>,
+    /// The inner size before `set_exclusive_fullscreen`, restored when leaving it.
+    windowed_size: Option<winit::dpi::PhysicalSize<u32>>,
+    render_mode: RenderMode,
+    /// How the scene pipelines write the color, see `set_blend`.
+    blend: BlendPreset,
+    write_mask: wgpu::ColorWrites,
+    /// Replaces the control flow of the render mode, see `set_control_flow`.
+    control_flow: Option<ControlFlow>,
+    /// The aspect ratio the scene is letterboxed to, see `set_target_aspect`.
+    target_aspect: Option<f32>,
+    letterbox_color: wgpu::Color,
+    /// Fills the bars, only created while there is a target aspect ratio.
+    letterbox_pipeline: Option<wgpu::RenderPipeline>,
+    /// The frame rate cap in `RenderMode::Continuous`, see `set_max_fps`.
+    max_fps: Option<u32>,
+    /// When the last frame started rendering, the next one is paced from here.
+    last_frame_start: Option<Instant>,
+    /// The latest size from `Resized` events that wasn't applied yet, see `apply_pending_resize`.
+    pending_resize: Option<winit::dpi::PhysicalSize<u32>>,
+    /// How often `resize` reconfigured the surface, for the tests.
+    #[cfg(test)]
+    resizes: u32,
+    /// Whether `resize` renders a frame right away, see `set_redraw_on_resize`.
+    redraw_on_resize: bool,
+    /// Whether `about_to_wait` polls the device, see `set_poll_every_frame`.
+    poll_every_frame: bool,
+    /// Set while a frame is rendered, so resizing from there doesn't render another one.
+    rendering: bool,
+    /// Set while the window has a zero size, nothing is rendered then.
+    minimized: bool,
+    /// Set while the window is fully hidden, nothing is rendered then either.
+    occluded: bool,
+    /// Set by `set_paused`.
+    paused: bool,
+    /// Whether the window got its first non-zero size, see `is_ready`.
+    ready: bool,
+    /// The grab mode that was applied by `set_cursor_grabbed`, `None` if not grabbed.
+    cursor_grab: CursorGrabMode,
+    /// Set with `set_cursor_icon` and `set_cursor_visible`, re-applied when regaining focus.
+    cursor_icon: CursorIcon,
+    cursor_visible: bool,
+    /// Physical pixels per logical pixel of the monitor the window is on.
+    scale_factor: f64,
+
+    // Last because it needs to be dropped after the surface. `None` in headless mode.
+    window: Option<Arc<Window>>,
+}
+
+impl State {
+    /// Prints the log records of this and other crates to stderr, filtered by `RUST_LOG`
+    /// (e.g. `debug`, `info` by default). Does nothing if the application already set up a
+    /// logger or without the `log` feature.
+    pub fn init_logging() {
+        #[cfg(feature = "log")]
+        logging::init();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(window: Arc<Window>) -> Result<Self, StateError> {
+        Self::new_with_config(window, StateConfig::default())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_with_config(window: Arc<Window>, config: StateConfig) -> Result<Self, StateError> {
+        Self::new_async(window, config).block_on()
+    }
+
+    /// Creates the state on a software adapter, see `StateConfig::software`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_software(window: Arc<Window>) -> Result<Self, StateError> {
+        Self::new_with_config(window, StateConfig::software())
+    }
+
+    /// Async version of `State::new_with_config`, the browser can't block on the adapter and device requests.
+    pub async fn new_async(
+        window: Arc<Window>,
+        mut config: StateConfig,
+    ) -> Result<Self, StateError> {
+        let instance = GpuContext::create_instance(&config);
+        let surface = Self::create_surface(&instance, &window)?;
+        let adapter = GpuContext::request_adapter(&instance, &mut config, Some(&surface)).await?;
+        let gpu = GpuContext::with_adapter(instance, adapter, &config).await?;
+        Self::with_surface(Arc::new(gpu), window, surface, config.prefer_srgb)
+    }
+
+    /// Creates the state with an adapter picked by the user, e.g. to prefer a discrete GPU.
+    /// `adapter` has to come from `instance`, e.g. through `Instance::enumerate_adapters`,
+    /// see also `enumerate_adapters`. The power preference and fallback options of `config`
+    /// are ignored.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_with_adapter(
+        window: Arc<Window>,
+        instance: Instance,
+        adapter: Adapter,
+        config: StateConfig,
+    ) -> Result<Self, StateError> {
+        let surface = Self::create_surface(&instance, &window)?;
+        if !adapter.is_surface_supported(&surface) {
+            return Err(StateError::IncompatibleAdapter);
+        }
+        let gpu = GpuContext::with_adapter(instance, adapter, &config).block_on()?;
+        Self::with_surface(Arc::new(gpu), window, surface, config.prefer_srgb)
+    }
+
+    /// Creates the state for another window that shares the device of `gpu`, only the
+    /// surface is created. Fails if the adapter can't present to the window.
+    /// An sRGB format is preferred, see `set_surface_format` for another one.
+    pub fn new_with_gpu(gpu: Arc<GpuContext>, window: Arc<Window>) -> Result<Self, StateError> {
+        let surface = Self::create_surface(&gpu.instance, &window)?;
+        if !gpu.adapter.is_surface_supported(&surface) {
+            return Err(StateError::IncompatibleAdapter);
+        }
+        Self::with_surface(gpu, window, surface, true)
+    }
+
+    fn with_surface(
+        gpu: Arc<GpuContext>,
+        window: Arc<Window>,
+        surface: Surface<'static>,
+        prefer_srgb: bool,
+    ) -> Result<Self, StateError> {
+        // winit applies a requested inner size when creating the window, so the first
+        // configuration already has it without waiting for a `Resized` event.
+        let size = Self::clamp_to_limits(&gpu, window.inner_size());
+        let surface_caps = surface.get_capabilities(&gpu.adapter);
+        let surface_format = Self::choose_surface_format(
+            &gpu.adapter,
+            &surface_caps,
+            prefer_srgb,
+            gpu.config.request_hdr,
+        )
+        .ok_or(StateError::NoSupportedFormat)?;
+        // Checked here, configuring the surface with an unsupported usage would panic.
+        let usage = gpu.config.surface_usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if !surface_caps.usages.contains(usage) {
+            return Err(StateError::UnsupportedSurfaceUsage(usage));
+        }
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            // Only empty if there are no formats either, Fifo is then just a placeholder.
+            present_mode: surface_caps
+                .present_modes
+                .first()
+                .copied()
+                .unwrap_or(wgpu::PresentMode::Fifo),
+            alpha_mode: Self::choose_alpha_mode(&surface_caps.alpha_modes, gpu.config.transparent),
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        log_info!(
+            "Surface format {:?}, present mode {:?}",
+            surface_config.format,
+            surface_config.present_mode
+        );
+
+        // Wayland windows get their real size with the first configure event, rendering
+        // before it can cause protocol errors.
+        let ready = !is_wayland(&window);
+        let mut state = Self {
+            surface_caps,
+            ready,
+            ..Self::from_parts(gpu, Some(surface), surface_config, Some(window))
+        };
+        // Windows can start with a zero size, e.g. on Wayland compositors before their first
+        // configure. The surface is then configured by the first non-zero `resize`, until then
+        // nothing is rendered.
+        state.minimized = size.width == 0 || size.height == 0;
+        state.surface_config.view_formats =
+            state.compatible_view_formats(state.gpu.config.view_formats.clone());
+        state.configure_surface();
+        if state.gpu.config.sample_count != 1 {
+            state.set_sample_count(state.gpu.config.sample_count);
+        }
+        Ok(state)
+    }
+
+    /// Picks a transparent alpha mode if wanted and supported, otherwise an opaque one.
+    fn choose_alpha_mode(
+        modes: &[wgpu::CompositeAlphaMode],
+        transparent: bool,
+    ) -> wgpu::CompositeAlphaMode {
+        let transparent_modes = [
+            wgpu::CompositeAlphaMode::PreMultiplied,
+            wgpu::CompositeAlphaMode::PostMultiplied,
+        ];
+        if let (true, Some(mode)) = (
+            transparent,
+            transparent_modes
+                .into_iter()
+                .find(|mode| modes.contains(mode)),
+        ) {
+            return mode;
+        }
+        // Only empty if there are no formats either, Opaque is then just a placeholder.
+        if modes.contains(&wgpu::CompositeAlphaMode::Opaque) {
+            wgpu::CompositeAlphaMode::Opaque
+        } else {
+            modes
+                .first()
+                .copied()
+                .unwrap_or(wgpu::CompositeAlphaMode::Opaque)
+        }
+    }
+
+    /// `mode` if supported, otherwise `Fifo`, which every surface supports.
+    fn choose_present_mode(
+        modes: &[wgpu::PresentMode],
+        mode: wgpu::PresentMode,
+    ) -> wgpu::PresentMode {
+        if modes.contains(&mode) {
+            mode
+        } else {
+            wgpu::PresentMode::Fifo
+        }
+    }
+
+    /// Picks a format the surface supports and that can be rendered to, HDR if requested and
+    /// otherwise sRGB or linear as preferred if possible. `None` if there is none, e.g. on
+    /// some software adapters.
+    fn choose_surface_format(
+        adapter: &Adapter,
+        surface_caps: &wgpu::SurfaceCapabilities,
+        prefer_srgb: bool,
+        request_hdr: bool,
+    ) -> Option<wgpu::TextureFormat> {
+        let mut formats = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .filter(|format| Self::is_renderable(adapter, *format))
+            .peekable();
+        let fallback = *formats.peek()?;
+        if request_hdr
+            && surface_caps.formats.contains(&HDR_FORMAT)
+            && Self::is_renderable(adapter, HDR_FORMAT)
+        {
+            return Some(HDR_FORMAT);
+        }
+        Some(
+            formats
+                .find(|format| format.is_srgb() == prefer_srgb)
+                .unwrap_or(fallback),
+        )
+    }
+
+    fn is_renderable(adapter: &Adapter, format: wgpu::TextureFormat) -> bool {
+        adapter
+            .get_texture_format_features(format)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+    }
+
+    fn create_surface(
+        instance: &Instance,
+        window: &Arc<Window>,
+    ) -> Result<Surface<'static>, StateError> {
+        // NOTE: Surface is created unsafe, make sure surface is destroyed before window.
+        #[cfg(not(target_arch = "wasm32"))]
+        let surface = unsafe {
+            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(window)?)?
+        };
+        // In the browser the surface is created from the canvas winit attached to the page.
+        #[cfg(target_arch = "wasm32")]
+        let surface = {
+            use winit::platform::web::WindowExtWebSys;
+            instance.create_surface(wgpu::SurfaceTarget::Canvas(window.canvas().unwrap()))?
+        };
+        Ok(surface)
+    }
+
+    /// Creates everything that doesn't depend on whether there is a window.
+    fn from_parts(
+        gpu: Arc<GpuContext>,
+        surface: Option<Surface<'static>>,
+        surface_config: SurfaceConfiguration,
+        window: Option<Arc<Window>>,
+    ) -> Self {
+        let device = &gpu.device;
+        let sample_count = 1;
+        let (depth_texture, depth_view) =
+            Self::create_depth_texture(device, &surface_config, sample_count);
+        let targets = TargetFormats {
+            color: surface_config.format,
+            depth: DEPTH_FORMAT,
+            sample_count,
+            blend: BlendPreset::default().blend_state(),
+            write_mask: wgpu::ColorWrites::ALL,
+        };
+        #[cfg(feature = "triangle")]
+        let render_pipeline = triangle::create_pipeline(device, targets);
+        let camera = camera::Camera::new(device);
+        let frame_uniforms = frame_uniforms::FrameUniformBuffer::new(device);
+        let instances = instance::Instances::new(device, &gpu.queue);
+        #[cfg(feature = "gpu-timing")]
+        let gpu_timer = gpu_timing::GpuTimer::new(device, &gpu.queue);
+        let mesh_pipeline = mesh::create_pipeline(device, &camera.bind_group_layout, targets);
+        let clear_color = wgpu::Color {
+            r: 0.1,
+            g: 0.2,
+            b: 0.3,
+            a: 1.0,
+        };
+        let headless_target = window
+            .is_none()
+            .then(|| headless::HeadlessTarget::new(device, &surface_config));
+        let frame_latency = surface_config.desired_maximum_frame_latency;
+
+        Self {
+            gpu,
+            surface,
+            surface_config,
+            // Set by `with_surface`, which needs it before.
+            surface_caps: wgpu::SurfaceCapabilities::default(),
+            headless_target,
+            depth_texture,
+            depth_view,
+            sample_count,
+            frame_latency,
+            msaa_view: None,
+            #[cfg(feature = "triangle")]
+            render_pipeline,
+            mesh_pipeline,
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            shader_watch: None,
+            mesh: None,
+            vertex_storage: None,
+            fxaa: None,
+            debug_lines: None,
+            sprite_batch: None,
+            #[cfg(feature = "text")]
+            text: None,
+            instances,
+            camera,
+            pending_camera: None,
+            frame_uniforms,
+            start_time: Instant::now(),
+            staging_belt: wgpu::util::StagingBelt::new(STAGING_CHUNK_SIZE),
+            staging_chunk_size: STAGING_CHUNK_SIZE,
+            color_targets: Vec::new(),
+            #[cfg(feature = "gpu-timing")]
+            gpu_timer,
+            occlusion_queries: None,
+            clear_color,
+            load_op: wgpu::LoadOp::Clear(clear_color),
+            frame_timer: FrameTimer::default(),
+            fixed_timestep: FixedTimestep::default(),
+            fps_in_title: false,
+            input: InputState::default(),
+            hovered_files: Vec::new(),
+            dropped_files: Vec::new(),
+            file_drop_callback: None,
+            device_lost_callback: None,
+            render_fn: Box::new(|context| context.draw_scene()),
+            render_graph: RenderGraph::default(),
+            frame_index: 0,
+            label_prefix: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            loader: loader::Loader::default(),
+            custom_events: Vec::new(),
+            fullscreen: false,
+            window_position: None,
+            windowed_size: None,
+            render_mode: RenderMode::default(),
+            blend: BlendPreset::default(),
+            write_mask: wgpu::ColorWrites::ALL,
+            control_flow: None,
+            target_aspect: None,
+            letterbox_color: wgpu::Color::BLACK,
+            letterbox_pipeline: None,
+            max_fps: None,
+            last_frame_start: None,
+            pending_resize: None,
+            #[cfg(test)]
+            resizes: 0,
+            redraw_on_resize: true,
+            poll_every_frame: false,
+            rendering: false,
+            minimized: false,
+            occluded: false,
+            paused: false,
+            ready: true,
+            cursor_grab: CursorGrabMode::None,
+            cursor_icon: CursorIcon::Default,
+            cursor_visible: true,
+            scale_factor: window.as_ref().map_or(1.0, |window| window.scale_factor()),
+            window,
+        }
+    }
+
+    /// The window rendered to, `None` in headless mode. Clones may outlive the state, its
+    /// surface is dropped before its reference to the window either way.
+    pub fn window(&self) -> Option<&Arc<Window>> {
+        self.window.as_ref()
+    }
+
+    /// The device, e.g. to create the textures for `add_color_target`.
+    pub fn device(&self) -> &Device {
+        &self.gpu.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.gpu.queue
+    }
+
+    /// The shared GPU context, pass it to `State::new_with_gpu` to render another window with
+    /// the same device.
+    pub fn gpu(&self) -> &Arc<GpuContext> {
+        &self.gpu
+    }
+
+    /// A shared layout for `descriptor`'s label and entries, see
+    /// `GpuContext::get_or_create_bind_group_layout`. The windows sharing a device share the
+    /// cache as well.
+    pub fn get_or_create_bind_group_layout(
+        &self,
+        descriptor: &wgpu::BindGroupLayoutDescriptor,
+    ) -> Arc<wgpu::BindGroupLayout> {
+        self.gpu.get_or_create_bind_group_layout(descriptor)
+    }
+
+    /// The limits the device was created with, see `GpuContext::summary` for a printable
+    /// overview.
+    pub fn device_limits(&self) -> &wgpu::Limits {
+        self.gpu.limits()
+    }
+
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.gpu.adapter_info()
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
+    /// Sets the background color, takes effect on the next rendered frame.
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.clear_color = color;
+        if let wgpu::LoadOp::Clear(_) = self.load_op {
+            self.load_op = wgpu::LoadOp::Clear(color);
+        }
+        self.request_redraw();
+    }
+
+    pub fn load_op(&self) -> wgpu::LoadOp<wgpu::Color> {
+        self.load_op
+    }
+
+    /// Sets how the main pass starts, `LoadOp::Load` keeps the previous frame, e.g. for
+    /// accumulation effects, and `LoadOp::Clear` also sets the clear color. Surface textures
+    /// don't keep their contents between frames, so `Load` is mainly useful in headless mode.
+    /// Color targets have their own `ColorTarget::load`.
+    pub fn set_load_op(&mut self, load_op: wgpu::LoadOp<wgpu::Color>) {
+        if let wgpu::LoadOp::Clear(color) = load_op {
+            self.clear_color = color;
+        }
+        self.load_op = load_op;
+        self.request_redraw();
+    }
+
+    /// Replaces the mesh drawn every frame, empty vertices or indices remove it.
+    /// The positions are transformed by the camera, see `update_camera`, and the triangles
+    /// are counter-clockwise.
+    pub fn upload_mesh(&mut self, vertices: &[Vertex], indices: &[u16]) {
+        self.mesh = mesh::Mesh::new(&self.gpu.device, vertices, indices);
+        self.request_redraw();
+    }
+
+    /// Draws the mesh once per instance with its model matrix, by default there is one
+    /// instance with the identity matrix. An empty slice hides the mesh.
+    pub fn set_instances(&mut self, instances: &[InstanceRaw]) {
+        self.instances
+            .upload(&self.gpu.device, &self.gpu.queue, instances);
+        self.request_redraw();
+    }
+
+    /// Sets the column-major view-projection matrix, call it every frame the camera moves.
+    /// It's uploaded with the next frame. It starts as the identity, so positions are in clip
+    /// space until this is called. There's no `glam` feature, math crates convert their
+    /// matrices, e.g. `glam::Mat4::to_cols_array_2d`.
+    pub fn update_camera(&mut self, view_proj: [[f32; 4]; 4]) {
+        self.pending_camera = Some(CameraUniform { view_proj });
+        self.request_redraw();
+    }
+
+    /// The belt the per-frame uploads go through, e.g. for own uniform buffers. Writes with
+    /// own encoders are finished and recalled together with the next frame, so submit them
+    /// before rendering it.
+    pub fn staging_belt(&mut self) -> &mut wgpu::util::StagingBelt {
+        &mut self.staging_belt
+    }
+
+    /// Replaces the staging belt with one allocating buffers of `chunk_size` bytes, larger
+    /// uploads get a buffer of their own. 64 KiB by default.
+    pub fn set_staging_chunk_size(&mut self, chunk_size: wgpu::BufferAddress) {
+        self.staging_chunk_size = chunk_size.max(1);
+        self.staging_belt = wgpu::util::StagingBelt::new(self.staging_chunk_size);
+    }
+
+    /// Copies the data that changed since the last frame through the staging belt, ahead of
+    /// the passes in `encoder`.
+    fn upload_frame_data(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(camera) = self.pending_camera.take() {
+            self.staging_belt
+                .write_buffer(
+                    encoder,
+                    &self.camera.buffer,
+                    0,
+                    wgpu::BufferSize::new(std::mem::size_of::<CameraUniform>() as u64).unwrap(),
+                    &self.gpu.device,
+                )
+                .copy_from_slice(bytemuck::bytes_of(&camera));
+        }
+        let frame_uniforms = self.frame_uniforms();
+        self.staging_belt
+            .write_buffer(
+                encoder,
+                &self.frame_uniforms.buffer,
+                0,
+                wgpu::BufferSize::new(std::mem::size_of::<FrameUniforms>() as u64).unwrap(),
+                &self.gpu.device,
+            )
+            .copy_from_slice(bytemuck::bytes_of(&frame_uniforms));
+        self.upload_debug_lines(encoder);
+        self.upload_sprites(encoder);
+    }
+
+    /// The values `render` uploads for the next frame, see `FrameUniforms`.
+    pub fn frame_uniforms(&self) -> FrameUniforms {
+        let width = self.surface_config.width.max(1) as f32;
+        let height = self.surface_config.height.max(1) as f32;
+        let mouse = match self.input.cursor_position() {
+            Some(position) => [position.x as f32 / width, position.y as f32 / height],
+            None => [-1.0, -1.0],
+        };
+        FrameUniforms {
+            // Wrapped in f64, which stays precise for far longer than any app runs.
+            time: (self.start_time.elapsed().as_secs_f64() % FrameUniforms::TIME_WRAP) as f32,
+            frame: self.frame_index as u32,
+            resolution: [width, height],
+            mouse,
+        }
+    }
+
+    /// The layout of the `FrameUniforms` bind group, for own pipelines that want to use it at
+    /// group 1, see `RenderContext::frame_uniforms_bind_group`.
+    pub fn frame_uniforms_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.frame_uniforms.bind_group_layout
+    }
+
+    /// The layout of the camera bind group, for own pipelines that want to use it at group 0.
+    pub fn camera_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.camera.bind_group_layout
+    }
+
+    /// The format of the depth buffer, render pipelines need to use it in their depth stencil state.
+    pub fn depth_format(&self) -> wgpu::TextureFormat {
+        DEPTH_FORMAT
+    }
+
+    /// The format of the surface, an sRGB one unless `StateConfig::prefer_srgb` is off or the
+    /// surface doesn't support it. Render pipelines need to use it as their color target.
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.surface_config.format
+    }
+
+    /// How the display shows the surface colors, HDR if the surface format is `Rgba16Float`,
+    /// e.g. from `StateConfig::request_hdr`. Only Vulkan (with `VK_EXT_swapchain_colorspace`),
+    /// Metal (EDR on macOS and iOS) and DX12 (scRGB) show HDR, the GL backend and the browser
+    /// always present SDR.
+    pub fn color_space(&self) -> ColorSpace {
+        if self.surface_config.format == HDR_FORMAT {
+            ColorSpace::ExtendedLinearSrgb
+        } else {
+            ColorSpace::Srgb
+        }
+    }
+
+    /// Whether the surface can be switched to HDR with `set_surface_format(Rgba16Float)`.
+    pub fn supports_hdr(&self) -> bool {
+        self.supported_surface_formats().contains(&HDR_FORMAT)
+            && Self::is_renderable(&self.gpu.adapter, HDR_FORMAT)
+    }
+
+    /// What the surface supports, queried when it was created. Empty in headless mode.
+    pub fn surface_capabilities(&self) -> &wgpu::SurfaceCapabilities {
+        &self.surface_caps
+    }
+
+    /// The formats the surface supports, see `set_surface_format`. Empty in headless mode.
+    pub fn supported_surface_formats(&self) -> &[wgpu::TextureFormat] {
+        &self.surface_caps.formats
+    }
+
+    /// Switches the surface to `format` and recreates the pipelines and render targets.
+    /// Returns `false` and keeps the current format if the surface doesn't support it or it
+    /// can't be rendered to. In headless mode any renderable format can be used.
+    pub fn set_surface_format(&mut self, format: wgpu::TextureFormat) -> bool {
+        if self.surface.is_some() && !self.supported_surface_formats().contains(&format)
+            || !Self::is_renderable(&self.gpu.adapter, format)
+        {
+            return false;
+        }
+        self.surface_config.format = format;
+        // View formats may only differ from the surface format in being sRGB or not.
+        self.surface_config
+            .view_formats
+            .retain(|view_format| view_format.remove_srgb_suffix() == format.remove_srgb_suffix());
+        self.configure_surface();
+        self.recreate_headless_target();
+        // Checks the sample count for the new format and recreates everything using it.
+        self.set_sample_count(self.sample_count);
+        true
+    }
+
+    /// The additional formats views of the surface texture can have.
+    pub fn view_formats(&self) -> &[wgpu::TextureFormat] {
+        &self.surface_config.view_formats
+    }
+
+    /// Allows creating views of the surface texture in other formats with
+    /// `RenderContext::view_with_format`. A linear surface with an sRGB view lets the GPU do
+    /// the gamma correction for pipelines writing linear colors, while the linear view keeps
+    /// writing already gamma corrected colors, e.g. UI colors taken as is. An sRGB surface
+    /// with a linear view works the other way around. Formats that differ from the surface
+    /// format in more than being sRGB are ignored, all of them if the backend doesn't
+    /// support view formats.
+    pub fn set_view_formats(&mut self, view_formats: Vec<wgpu::TextureFormat>) {
+        self.surface_config.view_formats = self.compatible_view_formats(view_formats);
+        self.configure_surface();
+        self.recreate_headless_target();
+    }
+
+    /// The formats of `view_formats` the surface texture can be viewed as.
+    fn compatible_view_formats(
+        &self,
+        view_formats: Vec<wgpu::TextureFormat>,
+    ) -> Vec<wgpu::TextureFormat> {
+        // Not every backend supports them, e.g. GL.
+        let required_flag = match self.surface {
+            Some(_) => wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS,
+            None => wgpu::DownlevelFlags::VIEW_FORMATS,
+        };
+        if !self
+            .gpu
+            .adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(required_flag)
+        {
+            return Vec::new();
+        }
+        let format = self.surface_config.format.remove_srgb_suffix();
+        view_formats
+            .into_iter()
+            .filter(|view_format| view_format.remove_srgb_suffix() == format)
+            .collect()
+    }
+
+    /// The MSAA sample count of the built-in passes, own pipelines drawing in them have to
+    /// use it in their `MultisampleState`.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// The depth texture of the built-in passes, with the size of the frame and
+    /// `sample_count` samples.
+    pub fn depth_texture(&self) -> &wgpu::Texture {
+        &self.depth_texture
+    }
+
+    /// Creates a pipeline for drawing in the built-in passes, e.g. from a render function.
+    /// Fails with a description of the mismatch if its sample count isn't `sample_count`,
+    /// instead of wgpu panicking when it's used. Recreate it after `set_sample_count`.
+    pub fn create_render_pipeline(
+        &self,
+        descriptor: &wgpu::RenderPipelineDescriptor,
+    ) -> Result<wgpu::RenderPipeline, PipelineError> {
+        if descriptor.multisample.count != self.sample_count {
+            return Err(PipelineError::SampleCountMismatch {
+                pipeline: descriptor.multisample.count,
+                state: self.sample_count,
+            });
+        }
+        Ok(self.gpu.device.create_render_pipeline(descriptor))
+    }
+
+    /// Sets the MSAA sample count, falls back to 1 (no MSAA) if the device doesn't support it
+    /// for the surface or depth format. Returns the sample count that was applied. 4 is
+    /// supported everywhere, 2, 8 and 16 only on some adapters and with
+    /// `Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`, see
+    /// `StateConfig::optional_features`. Recreates the pipelines and
+    /// render targets, the depth texture always gets the same sample count.
+    pub fn set_sample_count(&mut self, count: u32) -> u32 {
+        let supported = [self.surface_config.format, DEPTH_FORMAT]
+            .into_iter()
+            .all(|format| {
+                self.gpu
+                    .texture_format_features(format)
+                    .flags
+                    .sample_count_supported(count)
+            });
+        if !supported {
+            log_warn!("MSAA with {count} samples isn't supported, falling back to 1");
+        }
+        self.sample_count = if supported { count } else { 1 };
+        self.recreate_pipelines();
+        self.recreate_render_targets();
+        self.request_redraw();
+        self.sample_count
+    }
+
+    /// Recreates every pipeline drawing in the main render pass after the targets changed.
+    fn recreate_pipelines(&mut self) {
+        #[cfg(feature = "triangle")]
+        {
+            self.render_pipeline =
+                triangle::create_pipeline(&self.gpu.device, self.target_formats());
+        }
+        let targets = self.target_formats();
+        self.mesh_pipeline = self.create_mesh_pipeline(targets);
+        if self.target_aspect.is_some() {
+            self.letterbox_pipeline = Some(letterbox::create_pipeline(&self.gpu.device, targets));
+        }
+        if let Some(vertex_storage) = &mut self.vertex_storage {
+            vertex_storage.recreate_pipeline(
+                &self.gpu.device,
+                &self.camera.bind_group_layout,
+                targets,
+            );
+        }
+        if let Some(debug_lines) = &mut self.debug_lines {
+            debug_lines.recreate_pipeline(
+                &self.gpu.device,
+                &self.camera.bind_group_layout,
+                targets,
+            );
+        }
+        if let Some(sprite_batch) = &mut self.sprite_batch {
+            sprite_batch.recreate_pipeline(&self.gpu.device, targets);
+        }
+        if let Some(fxaa) = &mut self.fxaa {
+            fxaa.recreate_pipeline(&self.gpu.device, targets.color);
+        }
+    }
+
+    /// Creates the mesh pipeline with the built-in shader or the watched one.
+    fn create_mesh_pipeline(&self, targets: TargetFormats) -> wgpu::RenderPipeline {
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        if let Some(source) = self.shader_watch.as_ref().and_then(|watch| watch.source()) {
+            // It compiled before, the targets don't change that.
+            let shader = self
+                .gpu
+                .device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("Watched Shader"),
+                    source: wgpu::ShaderSource::Wgsl(source.into()),
+                });
+            return mesh::create_pipeline_with_shader(
+                &self.gpu.device,
+                &shader,
+                &self.camera.bind_group_layout,
+                targets,
+            );
+        }
+        mesh::create_pipeline(&self.gpu.device, &self.camera.bind_group_layout, targets)
+    }
+
+    fn target_formats(&self) -> TargetFormats {
+        TargetFormats {
+            color: self.surface_config.format,
+            depth: DEPTH_FORMAT,
+            sample_count: self.sample_count,
+            blend: self.blend.blend_state(),
+            write_mask: self.write_mask,
+        }
+    }
+
+    /// Recreates the depth and MSAA textures to match the surface size and sample count.
+    fn recreate_render_targets(&mut self) {
+        (self.depth_texture, self.depth_view) =
+            Self::create_depth_texture(&self.gpu.device, &self.surface_config, self.sample_count);
+        self.msaa_view =
+            Self::create_msaa_view(&self.gpu.device, &self.surface_config, self.sample_count);
+        if let Some(fxaa) = &mut self.fxaa {
+            fxaa.recreate_target(&self.gpu.device, &self.surface_config);
+        }
+    }
+
+    /// Creates a depth texture matching the surface size, zero dimensions are bumped to 1
+    /// because wgpu doesn't allow empty textures.
+    fn create_depth_texture(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        sample_count: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width: surface_config.width.max(1),
+                height: surface_config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    /// Creates the multisampled color target, `None` if `sample_count` is 1.
+    fn create_msaa_view(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        if sample_count == 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA Texture"),
+            size: wgpu::Extent3d {
+                width: surface_config.width.max(1),
+                height: surface_config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: surface_config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    pub fn input_mut(&mut self) -> &mut InputState {
+        &mut self.input
+    }
+
+    pub fn frame_timer(&self) -> &FrameTimer {
+        &self.frame_timer
+    }
+
+    pub fn frame_timer_mut(&mut self) -> &mut FrameTimer {
+        &mut self.frame_timer
+    }
+
+    /// The files that are dragged over the window, e.g. to highlight a drop zone.
+    pub fn hovered_files(&self) -> &[PathBuf] {
+        &self.hovered_files
+    }
+
+    /// Returns the files dropped on the window since the last call.
+    pub fn take_dropped_files(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.dropped_files)
+    }
+
+    /// Calls `callback` for every file dropped on the window, in addition to collecting
+    /// them for `take_dropped_files`.
+    pub fn set_file_drop_callback(&mut self, callback: impl FnMut(&Path) + 'static) {
+        self.file_drop_callback = Some(Box::new(callback));
+    }
+
+    /// Returns the `UserEvent::Custom` messages received since the last call.
+    pub fn take_custom_events(&mut self) -> Vec<Arc<dyn Any + Send + Sync>> {
+        std::mem::take(&mut self.custom_events)
+    }
+
+    /// The fixed timestep `update` is called with, 60 Hz by default.
+    pub fn fixed_timestep(&self) -> &FixedTimestep {
+        &self.fixed_timestep
+    }
+
+    pub fn fixed_timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.fixed_timestep
+    }
+
+    /// Advances the simulation by `dt`, called with the fixed step in `about_to_wait`
+    /// independent of the frame rate.
+    pub fn update(&mut self, _dt: Duration) {
+        // Put the simulation here, `render` can use `fixed_timestep().alpha()` to interpolate
+        // between the last two updates.
+    }
+
+    /// Shows the FPS in the window title, updated once per frame timer window.
+    pub fn set_fps_in_title(&mut self, fps_in_title: bool) {
+        self.fps_in_title = fps_in_title;
+    }
+
+    /// Physical pixels per logical pixel, use it to scale UI. Always 1 in headless mode.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Grabs the cursor, e.g. for a first-person camera, and returns the grab mode that was
+    /// used. `Locked` isn't supported everywhere, so it falls back to `Confined`.
+    /// The grab is re-applied when the window regains focus. Does nothing in headless mode.
+    pub fn set_cursor_grabbed(
+        &mut self,
+        grabbed: bool,
+    ) -> Result<CursorGrabMode, winit::error::ExternalError> {
+        let Some(window) = &self.window else {
+            return Ok(CursorGrabMode::None);
+        };
+        let mode = if grabbed {
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .map(|_| CursorGrabMode::Locked)
+                .or_else(|_| {
+                    window
+                        .set_cursor_grab(CursorGrabMode::Confined)
+                        .map(|_| CursorGrabMode::Confined)
+                })?
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)?;
+            CursorGrabMode::None
+        };
+        self.cursor_grab = mode;
+        Ok(mode)
+    }
+
+    /// The grab mode that is in effect, `None` if the cursor isn't grabbed.
+    pub fn cursor_grab_mode(&self) -> CursorGrabMode {
+        self.cursor_grab
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+        if let Some(window) = &self.window {
+            window.set_cursor_visible(visible);
+        }
+    }
+
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.cursor_icon
+    }
+
+    /// Changes the shape of the cursor over the window, e.g. to `CursorIcon::Pointer` while
+    /// hovering a button. Hide it with `set_cursor_visible`.
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.cursor_icon = icon;
+        if let Some(window) = &self.window {
+            window.set_cursor(icon);
+        }
+    }
+
+    /// Lets the input method compose text, e.g. for Chinese or Japanese, and show its
+    /// candidate window. Turn it on while a text field is focused, see
+    /// `InputState::take_text_input` and `InputState::preedit`.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        if let Some(window) = &self.window {
+            window.set_ime_allowed(allowed);
+        }
+    }
+
+    /// Where the text cursor is in physical pixels, the IME places its candidate window
+    /// next to it.
+    pub fn set_ime_cursor_area(
+        &self,
+        position: winit::dpi::PhysicalPosition<u32>,
+        size: winit::dpi::PhysicalSize<u32>,
+    ) {
+        if let Some(window) = &self.window {
+            window.set_ime_cursor_area(position, size);
+        }
+    }
+
+    /// Sets the window icon from 8 bit RGBA pixels, row by row from the top left.
+    /// Fails if `rgba` isn't `width * height * 4` bytes long. Does nothing in headless mode.
+    /// Decode PNG files with your own image crate first, see the crate docs.
+    pub fn set_icon_from_rgba(
+        &self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<(), winit::window::BadIcon> {
+        let icon = winit::window::Icon::from_rgba(rgba.to_vec(), width, height)?;
+        if let Some(window) = &self.window {
+            window.set_window_icon(Some(icon));
+        }
+        Ok(())
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    /// Switches between windowed and borderless fullscreen.
+    /// The surface is reconfigured by the `Resized` event that follows.
+    pub fn toggle_fullscreen(&mut self) {
+        if self.fullscreen {
+            self.exit_fullscreen();
+            return;
+        }
+        let Some(window) = &self.window else {
+            return;
+        };
+        self.fullscreen = true;
+        // If the monitor can't be queried winit picks the one the window is on by itself.
+        let monitor = window.current_monitor();
+        window.set_fullscreen(Some(Fullscreen::Borderless(monitor)));
+    }
+
+    /// Keeps the window above all others, e.g. for overlays. Not supported on every platform.
+    pub fn set_always_on_top(&self, on: bool) {
+        if let Some(window) = &self.window {
+            window.set_window_level(if on {
+                WindowLevel::AlwaysOnTop
+            } else {
+                WindowLevel::Normal
+            });
+        }
+    }
+
+    /// Shows or hides the title bar and borders.
+    pub fn set_decorations(&mut self, on: bool) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        window.set_decorations(on);
+        // The inner size may change with the borders, not every platform sends a `Resized`
+        // event for it.
+        let size = window.inner_size();
+        if (size.width, size.height) != (self.surface_config.width, self.surface_config.height) {
+            self.resize(size);
+        }
+    }
+
+    pub fn set_resizable(&self, on: bool) {
+        if let Some(window) = &self.window {
+            window.set_resizable(on);
+        }
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.surface_config.present_mode
+    }
+
+    /// The present modes the surface supports, `Fifo` is always among them.
+    /// Empty in headless mode.
+    pub fn supported_present_modes(&self) -> &[wgpu::PresentMode] {
+        &self.surface_caps.present_modes
+    }
+
+    /// Switches the present mode, e.g. `Fifo` for VSync or `Immediate` to turn it off.
+    /// Falls back to `Fifo` if the surface doesn't support the requested mode.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) {
+        self.surface_config.present_mode =
+            Self::choose_present_mode(self.supported_present_modes(), mode);
+        self.configure_surface();
+    }
+
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.surface_config.alpha_mode
+    }
+
+    /// The alpha modes the surface supports. Empty in headless mode.
+    pub fn supported_alpha_modes(&self) -> &[wgpu::CompositeAlphaMode] {
+        &self.surface_caps.alpha_modes
+    }
+
+    /// Switches how the compositor blends the window with what is behind it.
+    /// Falls back to an opaque mode if the surface doesn't support the requested one.
+    pub fn set_alpha_mode(&mut self, mode: wgpu::CompositeAlphaMode) {
+        let supported = self.supported_alpha_modes();
+        let alpha_mode = if supported.contains(&mode) {
+            mode
+        } else {
+            Self::choose_alpha_mode(supported, false)
+        };
+        self.surface_config.alpha_mode = alpha_mode;
+        self.configure_surface();
+        self.request_redraw();
+    }
+
+    /// Whether the alpha of the clear color shows what is behind the window.
+    pub fn is_transparent(&self) -> bool {
+        matches!(
+            self.surface_config.alpha_mode,
+            wgpu::CompositeAlphaMode::PreMultiplied | wgpu::CompositeAlphaMode::PostMultiplied
+        )
+    }
+
+    /// Makes the window transparent where the clear color's alpha is below 1, e.g. for overlays.
+    /// Stays opaque if the surface has no transparent alpha mode. On X11 the window has to be
+    /// created with `WindowAttributes::with_transparent(true)` as well.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        if let Some(window) = &self.window {
+            window.set_transparent(transparent);
+        }
+        self.surface_config.alpha_mode =
+            Self::choose_alpha_mode(self.supported_alpha_modes(), transparent);
+        self.configure_surface();
+        self.request_redraw();
+    }
+
+    /// The clear color of the surface, with the color multiplied by the alpha if the
+    /// compositor expects that.
+    fn surface_clear_color(&self) -> wgpu::Color {
+        self.surface_color(self.clear_color)
+    }
+
+    /// `color` how the surface expects it, premultiplied with the `PreMultiplied` alpha mode.
+    fn surface_color(&self, color: wgpu::Color) -> wgpu::Color {
+        if self.surface_config.alpha_mode != wgpu::CompositeAlphaMode::PreMultiplied {
+            return color;
+        }
+        wgpu::Color {
+            r: color.r * color.a,
+            g: color.g * color.a,
+            b: color.b * color.a,
+            a: color.a,
+        }
+    }
+
+    /// Recreates the surface from the window, reusing the adapter and device. Needed after
+    /// `suspended` on Android, where the old surface became invalid. Keeps the surface format
+    /// if the new surface supports it. Does nothing in headless mode.
+    pub fn reconfigure_surface(&mut self) -> Result<(), StateError> {
+        let Some(window) = &self.window else {
+            return Ok(());
+        };
+        // The old surface has to be gone before a new one is created for the same window.
+        self.surface = None;
+        let surface = Self::create_surface(&self.gpu.instance, window)?;
+        if !self.gpu.adapter.is_surface_supported(&surface) {
+            return Err(StateError::IncompatibleAdapter);
+        }
+        // The new surface may support other formats and modes, e.g. after resuming on Android.
+        self.surface_caps = surface.get_capabilities(&self.gpu.adapter);
+        if !self.surface_caps.usages.contains(self.surface_config.usage) {
+            return Err(StateError::UnsupportedSurfaceUsage(
+                self.surface_config.usage,
+            ));
+        }
+        // If the format is gone as well, another one is picked like for a new surface.
+        let format = if self
+            .surface_caps
+            .formats
+            .contains(&self.surface_config.format)
+        {
+            self.surface_config.format
+        } else {
+            Self::choose_surface_format(
+                &self.gpu.adapter,
+                &self.surface_caps,
+                self.surface_config.format.is_srgb(),
+                self.gpu.config.request_hdr,
+            )
+            .ok_or(StateError::NoSupportedFormat)?
+        };
+        self.surface = Some(surface);
+        // The window may have been resized while there was no surface.
+        let size = Self::clamp_to_limits(&self.gpu, window.inner_size());
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        self.set_minimized(size.width == 0 || size.height == 0);
+        if format != self.surface_config.format {
+            log_info!(
+                "The new surface doesn't support {:?}, switching to {format:?}",
+                self.surface_config.format
+            );
+            // Also recreates the pipelines for the new format.
+            self.set_surface_format(format);
+        }
+        self.configure_surface();
+        self.recreate_render_targets();
+        Ok(())
+    }
+
+    /// The frame latency requested with `set_frame_latency`.
+    pub fn frame_latency(&self) -> u32 {
+        self.frame_latency
+    }
+
+    /// How many frames the GPU may queue up before presenting, the requested frame latency
+    /// clamped to what the backend supports, see `set_frame_latency`.
+    pub fn effective_frame_latency(&self) -> u32 {
+        self.surface_config.desired_maximum_frame_latency
+    }
+
+    /// Sets how many frames may be queued up. 1 gives the lowest input latency, more give a
+    /// smoother frame rate when frame times vary. Defaults to 2. It's clamped to what the
+    /// backend supports, with a log message: 1 to 16 on DX12, 1 or 2 on Metal and 1 to 3 on
+    /// Vulkan and in the browser, where wgpu may clamp it further to the swapchain's image
+    /// counts. GL always uses 2.
+    pub fn set_frame_latency(&mut self, latency: u32) {
+        self.frame_latency = latency;
+        let backend = self.gpu.adapter.get_info().backend;
+        let effective = clamp_frame_latency(backend, latency);
+        if effective != latency {
+            log_info!("Clamped the frame latency {latency} to {effective} on {backend:?}");
+        }
+        self.surface_config.desired_maximum_frame_latency = effective;
+        // The surface texture only lives during `render`, so none is lost by reconfiguring.
+        self.configure_surface();
+    }
+
+    /// Clamps `size` to the largest texture the device supports. Software adapters may only
+    /// support 2048 pixels, the compositor then stretches the surface over larger windows.
+    fn clamp_to_limits(
+        gpu: &GpuContext,
+        size: winit::dpi::PhysicalSize<u32>,
+    ) -> winit::dpi::PhysicalSize<u32> {
+        let max_size = gpu.limits().max_texture_dimension_2d;
+        winit::dpi::PhysicalSize::new(size.width.min(max_size), size.height.min(max_size))
+    }
+
+    /// Applies `surface_config` to the surface, skipped while the window has a zero size.
+    fn configure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            if self.surface_config.width > 0 && self.surface_config.height > 0 {
+                surface.configure(&self.gpu.device, &self.surface_config);
+            }
+        }
+    }
+
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Switches between rendering continuously and on demand, takes effect immediately.
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+        self.request_redraw();
+    }
+
+    pub fn max_fps(&self) -> Option<u32> {
+        self.max_fps
+    }
+
+    /// Caps the frame rate in `RenderMode::Continuous`, e.g. to not spin the GPU when vsync is
+    /// off. `None` or 0 renders as fast as possible.
+    pub fn set_max_fps(&mut self, fps: Option<u32>) {
+        self.max_fps = fps.filter(|&fps| fps > 0);
+        self.request_redraw();
+    }
+
+    /// When the next frame may start, `None` if it may start right away.
+    fn next_frame_time(&self) -> Option<Instant> {
+        let max_fps = self.max_fps?;
+        // Measured from the start of the last frame, so the time it took counts towards the
+        // frame time instead of adding to it.
+        Some(self.last_frame_start? + Duration::from_secs(1) / max_fps)
+    }
+
+    /// Replaces the control flow that follows the render mode, `None` goes back to it:
+    /// `RenderMode::Continuous` uses `Poll`, or `WaitUntil` the next frame with a frame rate
+    /// cap, and `RenderMode::OnDemand` uses `Wait`. `Poll` keeps a CPU core busy even when
+    /// nothing changes, `Wait` sleeps until the next event and uses the least power, and
+    /// `WaitUntil` sleeps until the next event or the given time, e.g. for a timer. The
+    /// control flow is set in every `about_to_wait`, so a new one takes effect in the next
+    /// iteration of the event loop.
+    pub fn set_control_flow(&mut self, control_flow: Option<ControlFlow>) {
+        self.control_flow = control_flow;
+    }
+
+    /// The control flow this state needs until the next frame, see `set_control_flow`.
+    pub fn control_flow(&self) -> ControlFlow {
+        if let Some(control_flow) = self.control_flow {
+            return control_flow;
+        }
+        match self.render_mode {
+            RenderMode::Continuous => match self.next_frame_time() {
+                Some(next_frame_time) if next_frame_time > Instant::now() => {
+                    ControlFlow::WaitUntil(next_frame_time)
+                }
+                // `Wait` would block until the next event, continuous rendering doesn't want that.
+                _ => ControlFlow::Poll,
+            },
+            RenderMode::OnDemand => {
+                // Woken up for the next check of the watched shader.
+                #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+                if let Some(watch) = &self.shader_watch {
+                    return ControlFlow::WaitUntil(watch.next_check());
+                }
+                ControlFlow::Wait
+            }
+        }
+    }
+
+    /// Asks for a new frame, needed in `RenderMode::OnDemand` after changing what is drawn.
+    /// Does nothing in headless mode.
+    pub fn request_redraw(&self) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Whether rendering is paused because the window is minimized.
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Whether rendering is paused because the window is fully hidden, e.g. behind other
+    /// windows on macOS or on another workspace on Wayland.
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    /// Whether frames can be rendered to the window. On Wayland that's only after the first
+    /// non-zero `Resized`, before it `render` does nothing. Always true elsewhere and in
+    /// headless mode.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Whether nothing is rendered, because of `set_paused` or because the window is
+    /// minimized or occluded.
+    pub fn is_paused(&self) -> bool {
+        self.paused || self.minimized || self.occluded
+    }
+
+    /// Freezes the window on its last frame, e.g. while a modal dialog is open. Input events
+    /// are still handled, but no frames are rendered or requested until it's resumed.
+    pub fn set_paused(&mut self, paused: bool) {
+        let was_paused = self.is_paused();
+        self.paused = paused;
+        self.redraw_if_resumed(was_paused);
+    }
+
+    /// Pauses or resumes rendering, resuming requests a redraw to restart the render loop.
+    fn set_minimized(&mut self, minimized: bool) {
+        let was_paused = self.is_paused();
+        self.minimized = minimized;
+        self.redraw_if_resumed(was_paused);
+    }
+
+    /// Like `set_minimized`, but the window keeps its size, so the surface stays configured
+    /// and can be rendered to right away when it's visible again.
+    fn set_occluded(&mut self, occluded: bool) {
+        let was_paused = self.is_paused();
+        self.occluded = occluded;
+        self.redraw_if_resumed(was_paused);
+    }
+
+    fn redraw_if_resumed(&mut self, was_paused: bool) {
+        if !was_paused || self.is_paused() {
+            return;
+        }
+        // The pause would otherwise show up as one very long frame.
+        self.frame_timer.restart();
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Remembers the latest size of a burst of `Resized` events, it's applied once by
+    /// `apply_pending_resize`.
+    fn queue_resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        self.pending_resize = Some(size);
+    }
+
+    /// Applies the latest size from the `Resized` events since the last call, if any.
+    fn apply_pending_resize(&mut self) {
+        if let Some(size) = self.pending_resize.take() {
+            self.resize(size);
+        }
+    }
+
+    /// Resizes the surface and render targets. A zero size, e.g. when the window is minimized,
+    /// pauses rendering until the next non-zero size.
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        self.set_minimized(new_size.width == 0 || new_size.height == 0);
+        if new_size.width > 0 && new_size.height > 0 {
+            self.ready = true;
+            #[cfg(test)]
+            {
+                self.resizes += 1;
+            }
+            let new_size = Self::clamp_to_limits(&self.gpu, new_size);
+            self.surface_config.width = new_size.width;
+            self.surface_config.height = new_size.height;
+            self.configure_surface();
+            self.recreate_render_targets();
+            self.recreate_headless_target();
+            self.request_redraw();
+            // Otherwise the reconfigured surface shows up empty until the next redraw.
+            if self.redraw_on_resize && !self.rendering && self.renders_to_window() {
+                self.rendering = true;
+                self.start_frame();
+                if let Err(err) = self.render() {
+                    log_warn!("Failed to redraw after resizing: {err}");
+                }
+                self.rendering = false;
+            }
+        }
+    }
+
+    /// Renders a frame right after every resize, on by default. Avoids flashing during
+    /// live resizes on Windows and macOS.
+    pub fn set_redraw_on_resize(&mut self, redraw_on_resize: bool) {
+        self.redraw_on_resize = redraw_on_resize;
+    }
+
+    /// Registers a target the scene is drawn into every frame before the surface, cleared or
+    /// loaded with `load`. See `ColorTarget` for its requirements.
+    pub fn add_color_target(&mut self, view: wgpu::TextureView, load: wgpu::LoadOp<wgpu::Color>) {
+        self.color_targets.push(ColorTarget {
+            view,
+            resolve_target: None,
+            load,
+        });
+    }
+
+    /// The registered targets, e.g. to set a resolve target or to remove some.
+    pub fn color_targets_mut(&mut self) -> &mut Vec<ColorTarget> {
+        &mut self.color_targets
+    }
+
+    /// Records the passes for the registered color targets and then the main render pass
+    /// drawing into `view`, which has to use the surface format. With MSAA the main pass
+    /// draws into `msaa_view` and resolves it into `view`, it has to have the size of `view`
+    /// and the current sample count. Without `queries` no timestamps or occlusion queries
+    /// are written, for frames that aren't submitted with `submit_frame`.
+    fn encode_render_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        msaa_view: Option<&wgpu::TextureView>,
+        queries: bool,
+    ) {
+        let timestamp_writes = |first, last| self.timestamp_writes(first, last).filter(|_| queries);
+        // The color targets are drawn without occlusion queries, they may have another size
+        // than the frame.
+        for (i, target) in self.color_targets.iter().enumerate() {
+            self.encode_scene_pass(
+                encoder,
+                target.attachment(),
+                timestamp_writes(i == 0, false),
+                None,
+                false,
+            );
+        }
+        let load = match self.load_op {
+            wgpu::LoadOp::Clear(_) => wgpu::LoadOp::Clear(self.surface_clear_color()),
+            wgpu::LoadOp::Load => wgpu::LoadOp::Load,
+        };
+        // With MSAA, render into the multisampled texture and resolve it into the surface.
+        let attachment = match msaa_view {
+            Some(msaa_view) => wgpu::RenderPassColorAttachment {
+                view: msaa_view,
+                resolve_target: Some(view),
+                ops: wgpu::Operations {
+                    load,
+                    // Only the resolved surface texture is needed afterwards, unless the next
+                    // frame loads the multisampled one.
+                    store: match load {
+                        wgpu::LoadOp::Clear(_) => wgpu::StoreOp::Discard,
+                        wgpu::LoadOp::Load => wgpu::StoreOp::Store,
+                    },
+                },
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            },
+        };
+        let first = self.color_targets.is_empty();
+        self.encode_scene_pass(
+            encoder,
+            attachment,
+            timestamp_writes(first, true),
+            self.occlusion_query_set().filter(|_| queries),
+            true,
+        );
+    }
+
+    /// The timestamp writes of a pass for the GPU frame time, `None` without `gpu-timing`.
+    fn timestamp_writes(
+        &self,
+        first: bool,
+        last: bool,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        #[cfg(feature = "gpu-timing")]
+        if let Some(gpu_timer) = &self.gpu_timer {
+            return Some(gpu_timer.timestamp_writes(first, last));
+        }
+        // Unused without `gpu-timing`.
+        let _ = (first, last);
+        None
+    }
+
+    /// Records a render pass drawing the scene into `color_attachment`. Only the main pass
+    /// is letterboxed, the color targets may have another size than the frame.
+    fn encode_scene_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        color_attachment: wgpu::RenderPassColorAttachment,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites>,
+        occlusion_query_set: Option<&wgpu::QuerySet>,
+        main_pass: bool,
+    ) {
+        let label = self.frame_label("Render Pass");
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: label.as_deref(),
+            color_attachments: &[Some(color_attachment)],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set,
+            timestamp_writes,
+        });
+        if main_pass {
+            self.set_letterbox_viewport(&mut render_pass);
+        }
+        #[cfg(feature = "triangle")]
+        {
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+        if let Some(mesh) = &self.mesh {
+            render_pass.set_pipeline(&self.mesh_pipeline);
+            render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
+            mesh.draw(&mut render_pass, &self.instances);
+        }
+        if let Some(vertex_storage) = &self.vertex_storage {
+            render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
+            vertex_storage.draw(&mut render_pass);
+        }
+        if let Some(debug_lines) = &self.debug_lines {
+            render_pass.set_bind_group(0, &self.camera.bind_group, &[]);
+            debug_lines.draw(&mut render_pass);
+        }
+        if main_pass {
+            if let Some(sprite_batch) = &self.sprite_batch {
+                sprite_batch.draw(&mut render_pass);
+            }
+            self.draw_letterbox_bars(&mut render_pass);
+        }
+    }
+
+    /// Submits the commands of a frame encoded with `encode_render_pass`.
+    fn submit_frame(&mut self, mut encoder: wgpu::CommandEncoder) {
+        let occlusion_slot = self
+            .occlusion_queries
+            .as_mut()
+            .and_then(|occlusion_queries| {
+                occlusion_queries.collect(&self.gpu.device);
+                occlusion_queries.resolve(&mut encoder)
+            });
+        #[cfg(feature = "gpu-timing")]
+        let timestamp_slot = self.gpu_timer.as_mut().and_then(|gpu_timer| {
+            gpu_timer.collect(&self.gpu.device);
+            gpu_timer.resolve(&mut encoder)
+        });
+        // The belt's buffers have to be unmapped before the frame using them is submitted,
+        // and can only be reused once the GPU is done with that frame, which `recall` tracks.
+        self.staging_belt.finish();
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.staging_belt.recall();
+        self.frame_index += 1;
+        if let (Some(occlusion_queries), Some(slot)) = (&self.occlusion_queries, occlusion_slot) {
+            occlusion_queries.map(slot);
+        }
+        #[cfg(feature = "gpu-timing")]
+        if let (Some(gpu_timer), Some(slot)) = (&self.gpu_timer, timestamp_slot) {
+            gpu_timer.map(slot);
+        }
+    }
+
+    /// How long the GPU took for the render passes of a recent frame, a few frames behind
+    /// because the timestamps are read back without blocking. `None` until the first one
+    /// arrives or if the device doesn't support timestamp queries.
+    #[cfg(feature = "gpu-timing")]
+    pub fn last_gpu_frame_time(&self) -> Option<Duration> {
+        self.gpu_timer.as_ref()?.last_frame_time()
+    }
+
+    /// The number of frames rendered so far, the index of the next frame.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Puts `prefix` in front of the encoder and render pass labels, e.g. `"Editor "` for
+    /// `"Editor Render Pass #123"`, to tell windows apart in RenderDoc, PIX or Xcode captures.
+    pub fn set_label_prefix(&mut self, prefix: Option<String>) {
+        self.label_prefix = prefix;
+    }
+
+    /// The debug label of `name` in the current frame, like `"Render Pass #123"`.
+    #[cfg(not(feature = "release-labels-off"))]
+    fn frame_label(&self, name: &str) -> Option<String> {
+        let prefix = self.label_prefix.as_deref().unwrap_or_default();
+        Some(format!("{prefix}{name} #{}", self.frame_index))
+    }
+
+    /// Labels are left out with `release-labels-off`, so no strings are formatted per frame.
+    #[cfg(feature = "release-labels-off")]
+    fn frame_label(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    /// Renders a frame to the window. Does nothing while paused, see `is_paused`, while the
+    /// window has a zero size or isn't ready yet, see `is_ready`, or in headless mode, see
+    /// `render_to_texture`.
+    /// Getting the surface texture is retried a few times, reconfiguring the surface if it's
+    /// lost or outdated, so only repeated failures are returned.
+    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        if !self.renders_to_window() {
+            return Ok(());
+        }
+        let output = self.acquire_surface_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let label = self.frame_label("Render Encoder");
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: label.as_deref(),
+            });
+        self.upload_frame_data(&mut encoder);
+        // Taken out for the call, they get the state immutably.
+        let mut render_fn = std::mem::replace(&mut self.render_fn, Box::new(|_| {}));
+        let mut render_graph = std::mem::take(&mut self.render_graph);
+        self.run_render_fn(
+            &mut render_fn,
+            &mut render_graph,
+            &mut encoder,
+            self.frame_target(&output.texture, &view),
+        );
+        self.render_fn = render_fn;
+        self.render_graph = render_graph;
+        self.submit_frame(encoder);
+        output.present();
+
+        Ok(())
+    }
+
+    /// Counts the frame `render` is about to render in the frame timer and paces the next one
+    /// from it. Shows the FPS in the title, see `set_fps_in_title`.
+    fn start_frame(&mut self) {
+        self.last_frame_start = Some(Instant::now());
+        self.frame_timer.tick();
+        if self.fps_in_title
+            && self
+                .frame_timer
+                .frame_count()
+                .is_multiple_of(self.frame_timer.window_size() as u64)
+        {
+            if let Some(window) = &self.window {
+                window.set_title(&format!("{:.0} FPS", self.frame_timer.fps()));
+            }
+        }
+    }
+
+    /// Whether `render` renders a frame, not while paused, before the window is ready or in
+    /// headless mode.
+    fn renders_to_window(&self) -> bool {
+        self.surface.is_some() && self.ready && !self.is_paused()
+    }
+
+    /// Gets the surface texture, retrying after timeouts and after reconfiguring a lost or
+    /// outdated surface. Compositors sometimes fail once under load, e.g. on Wayland, and
+    /// would otherwise drop the frame.
+    fn acquire_surface_texture(&mut self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+        let mut attempt = 1;
+        loop {
+            let Some(surface) = &self.surface else {
+                return Err(wgpu::SurfaceError::Lost);
+            };
+            let err = match surface.get_current_texture() {
+                Ok(output) => return Ok(output),
+                Err(err) => err,
+            };
+            let recovery = surface_recovery(&err);
+            if recovery == SurfaceRecovery::Exit || attempt == SURFACE_TEXTURE_ATTEMPTS {
+                return Err(err);
+            }
+            log_warn!(
+                "{err} getting the surface texture, retrying ({attempt}/{SURFACE_TEXTURE_ATTEMPTS})"
+            );
+            if recovery == SurfaceRecovery::Reconfigure {
+                self.update_surface_size();
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Handles the error of a frame that `render` gave up on, reconfiguring the surface with
+    /// the window's `size` if it's lost or outdated. The caller exits on `SurfaceRecovery::Exit`.
+    fn recover_from_surface_error(
+        &mut self,
+        err: &wgpu::SurfaceError,
+        size: winit::dpi::PhysicalSize<u32>,
+    ) -> SurfaceRecovery {
+        let recovery = surface_recovery(err);
+        match recovery {
+            // The next requested redraw will use the new config.
+            SurfaceRecovery::Reconfigure => {
+                log_warn!("{err}, reconfiguring the surface");
+                self.resize(size);
+            }
+            // Skip this frame, the next one is requested after the frame or on the next
+            // change.
+            SurfaceRecovery::Retry => {
+                log_debug!("{err}, skipping a frame");
+            }
+            SurfaceRecovery::Exit => {
+                log_warn!("{err}, exiting");
+            }
+        }
+        recovery
+    }
+
+    /// Reconfigures the surface, with the window's current size if it changed. Unlike
+    /// `resize` it doesn't redraw, since it's called while rendering.
+    fn update_surface_size(&mut self) {
+        if let Some(window) = &self.window {
+            let size = Self::clamp_to_limits(&self.gpu, window.inner_size());
+            let changed = (size.width, size.height)
+                != (self.surface_config.width, self.surface_config.height);
+            if changed && size.width > 0 && size.height > 0 {
+                self.surface_config.width = size.width;
+                self.surface_config.height = size.height;
+                self.recreate_render_targets();
+            }
+        }
+        self.configure_surface();
+    }
+
+    /// Polls the device, which runs the callbacks of finished `map_async` calls and frees
+    /// resources the GPU is done with. `Maintain::Wait` blocks until all submitted work is done.
+    pub fn poll(&self, maintain: wgpu::Maintain) -> wgpu::MaintainResult {
+        self.gpu.device.poll(maintain)
+    }
+
+    /// Polls the device without blocking in every `about_to_wait`, off by default. Native
+    /// backends only call `map_async` callbacks while the device is polled, so readbacks that
+    /// wait for them would otherwise never finish unless something else polls.
+    pub fn set_poll_every_frame(&mut self, poll_every_frame: bool) {
+        self.poll_every_frame = poll_every_frame;
+    }
+
+    /// Renders and presents one frame like `render`, or into the headless texture in headless
+    /// mode, and waits until the GPU is done with it, e.g. for benchmarks. Returns the time
+    /// from recording to the GPU finishing, or `None` if `render` rendered nothing, e.g.
+    /// while paused or before the window is ready.
+    pub fn render_once_blocking(&mut self) -> Result<Option<Duration>, wgpu::SurfaceError> {
+        let start = Instant::now();
+        if self.headless_target.is_some() {
+            self.render_to_texture();
+        } else if self.renders_to_window() {
+            self.render()?;
+        } else {
+            return Ok(None);
+        }
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+        Ok(Some(start.elapsed()))
+    }
+}
+
+/// The handles of the window, for libraries that create their own surface or overlay on it.
+/// `Unavailable` in headless mode. Clone `State::window` to keep the window alive longer.
+impl wgpu::rwh::HasWindowHandle for State {
+    fn window_handle(&self) -> Result<wgpu::rwh::WindowHandle<'_>, wgpu::rwh::HandleError> {
+        match &self.window {
+            Some(window) => window.window_handle(),
+            None => Err(wgpu::rwh::HandleError::Unavailable),
+        }
+    }
+}
+
+impl wgpu::rwh::HasDisplayHandle for State {
+    fn display_handle(&self) -> Result<wgpu::rwh::DisplayHandle<'_>, wgpu::rwh::HandleError> {
+        match &self.window {
+            Some(window) => window.display_handle(),
+            None => Err(wgpu::rwh::HandleError::Unavailable),
+        }
+    }
+}
+
+/// We only implement the `ApplicationHandler` trait for the `State` struct so that we can
+/// handle the events that are sent to the application in the `State` struct implementations.
+/// This is so we can access documentation in our code editors on the trait methods directly.
+impl ApplicationHandler<UserEvent> for State {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        // The surface is dropped in `suspended`, so it has to be recreated when resuming.
+        if self.surface.is_some() {
+            return;
+        }
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+        log_info!("Recreating the surface after resuming");
+        if let Err(err) = self.reconfigure_surface() {
+            log_error!("Failed to recreate the surface: {err}");
+            event_loop.exit();
+            return;
+        }
+        window.request_redraw();
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        // Window events are only sent for windows, so there is nothing to do in headless mode.
+        let Some(window) = self.window.clone() else {
+            return;
+        };
+        self.input.handle_window_event(&event);
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            // Dragging the window border sends a burst of these, so only the latest size is
+            // applied once in `about_to_wait` or before the next frame.
+            WindowEvent::Resized(_) => self.queue_resize(window.inner_size()),
+            // Moving to a monitor with a different DPI changes the physical size of the window.
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.scale_factor = scale_factor;
+                self.queue_resize(window.inner_size());
+            }
+            WindowEvent::HoveredFile(path) => self.hovered_files.push(path),
+            WindowEvent::HoveredFileCancelled => self.hovered_files.clear(),
+            WindowEvent::DroppedFile(path) => {
+                self.hovered_files.retain(|hovered| *hovered != path);
+                if let Some(callback) = &mut self.file_drop_callback {
+                    callback(&path);
+                }
+                self.dropped_files.push(path);
+            }
+            // Some platforms reset the cursor and release the grab when the window loses focus.
+            WindowEvent::Focused(true) => {
+                window.set_cursor(self.cursor_icon);
+                window.set_cursor_visible(self.cursor_visible);
+                if self.cursor_grab != CursorGrabMode::None {
+                    if let Err(err) = window.set_cursor_grab(self.cursor_grab) {
+                        log_warn!("Failed to grab the cursor again: {err}");
+                    }
+                }
+            }
+            // Hidden windows don't need to be rendered, e.g. on another virtual desktop.
+            WindowEvent::Occluded(occluded) => self.set_occluded(occluded),
+            WindowEvent::Moved(position) => self.window_position = Some(position),
+            WindowEvent::RedrawRequested => {
+                self.rendering = true;
+                // The frame has to have the latest size, even mid-burst.
+                self.apply_pending_resize();
+                // Don't request the next redraw, so the app idles until it is visible again.
+                // A lost device is recreated in `about_to_wait`, which requests the next one.
+                if self.is_paused() || self.gpu.is_lost() {
+                    self.rendering = false;
+                    return;
+                }
+                self.start_frame();
+                if let Err(err) = self.render() {
+                    let recovery = self.recover_from_surface_error(&err, window.inner_size());
+                    if recovery == SurfaceRecovery::Exit {
+                        event_loop.exit();
+                    }
+                }
+                self.rendering = false;
+                // With a frame rate cap, `about_to_wait` requests the next frame once it's due.
+                if self.render_mode == RenderMode::Continuous && self.max_fps.is_none() {
+                    window.request_redraw();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {}
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::Redraw => self.request_redraw(),
+            UserEvent::LoadFinished => self.finish_loads(),
+            UserEvent::Custom(message) => self.custom_events.push(message),
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        _event: DeviceEvent,
+    ) {
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.gpu.is_lost() {
+            self.recover_from_device_loss(event_loop);
+        }
+        self.apply_pending_resize();
+        self.finish_loads();
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        self.check_watched_shader();
+        if self.poll_every_frame {
+            self.poll(wgpu::Maintain::Poll);
+        }
+        let control_flow = self.control_flow();
+        // While minimized the next frame is requested once the window is visible again.
+        if self.max_fps.is_some() && control_flow == ControlFlow::Poll && !self.is_paused() {
+            self.request_redraw();
+        }
+        event_loop.set_control_flow(control_flow);
+        for _ in 0..self.fixed_timestep.advance() {
+            self.update(self.fixed_timestep.step());
+        }
+        self.input.end_frame();
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        // On Android the surface becomes invalid once the app is suspended.
+        self.surface = None;
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
+
+    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
+}
+
+/// What is done about a failure to get the surface texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SurfaceRecovery {
+    /// The surface is lost or doesn't match the window anymore, e.g. after a rapid resize.
+    Reconfigure,
+    /// The compositor didn't hand out a texture in time, the next try may get one.
+    Retry,
+    /// Trying again won't help.
+    Exit,
+}
+
+fn surface_recovery(err: &wgpu::SurfaceError) -> SurfaceRecovery {
+    match err {
+        wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => SurfaceRecovery::Reconfigure,
+        wgpu::SurfaceError::Timeout => SurfaceRecovery::Retry,
+        wgpu::SurfaceError::OutOfMemory => SurfaceRecovery::Exit,
+    }
+}
+
+/// The frame latencies `backend` honors, like wgpu reports them to itself. Vulkan's depend on
+/// the surface, which wgpu doesn't expose.
+fn frame_latency_range(backend: wgpu::Backend) -> std::ops::RangeInclusive<u32> {
+    match backend {
+        wgpu::Backend::Dx12 => 1..=16,
+        wgpu::Backend::Metal => 1..=2,
+        wgpu::Backend::Gl => 2..=2,
+        _ => 1..=3,
+    }
+}
+
+fn clamp_frame_latency(backend: wgpu::Backend, latency: u32) -> u32 {
+    let range = frame_latency_range(backend);
+    latency.clamp(*range.start(), *range.end())
+}
+
+/// Whether `window` is a Wayland window, as opposed to X11 or another platform.
+fn is_wayland(window: &Window) -> bool {
+    use wgpu::rwh::{HasDisplayHandle, RawDisplayHandle};
+    matches!(
+        window.display_handle().map(|handle| handle.as_raw()),
+        Ok(RawDisplayHandle::Wayland(_))
+    )
+}
+
+/// Lists the adapters of all backends, e.g. for a GPU selection menu. Pass the chosen adapter to
+/// `State::new_with_adapter`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn enumerate_adapters() -> Vec<wgpu::AdapterInfo> {
+    let config = StateConfig::default();
+    GpuContext::create_instance(&config)
+        .enumerate_adapters(config.effective_backends())
+        .iter()
+        .map(|adapter| adapter.get_info())
+        .collect()
+}
+
+/// Runs one `State` per window, the first window is opened on the first `resumed`.
+#[derive(Default)]
+pub struct WinitWrapper {
+    /// Used to create the first window, e.g. for its title and size.
+    window_attributes: WindowAttributes,
+    /// Applied to every state once it is created.
+    render_mode: RenderMode,
+    /// Applied to every state once it is created and to the event loop when it starts.
+    control_flow: Option<ControlFlow>,
+    /// Whether the first window was opened.
+    started: bool,
+    /// Created with the first window and shared by the others.
+    gpu: Option<Arc<GpuContext>>,
+    /// Given to every state, see `set_event_loop_proxy`.
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<winit::event_loop::EventLoopProxy<UserEvent>>,
+    states: HashMap<WindowId, State>,
+    /// On wasm the states are created asynchronously and handed over through this slot.
+    #[cfg(target_arch = "wasm32")]
+    pending_states: Rc<RefCell<Vec<State>>>,
+}
+
+impl WinitWrapper {
+    /// Creates the first window with `window_attributes` instead of the platform defaults, e.g.
+    /// `Window::default_attributes().with_title("Title").with_inner_size(LogicalSize::new(800, 600))`.
+    pub fn new(window_attributes: WindowAttributes) -> Self {
+        Self {
+            window_attributes,
+            ..Default::default()
+        }
+    }
+
+    /// Switches between rendering continuously and on demand in all windows, also before
+    /// they are opened.
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+        for state in self.states().values_mut() {
+            state.set_render_mode(render_mode);
+        }
+    }
+
+    /// Replaces the control flow of the render mode in all windows, also before they are
+    /// opened, see `State::set_control_flow`.
+    pub fn set_control_flow(&mut self, control_flow: Option<ControlFlow>) {
+        self.control_flow = control_flow;
+        for state in self.states().values_mut() {
+            state.set_control_flow(control_flow);
+        }
+    }
+
+    /// Lets the `spawn_load` jobs of all windows wake the event loop, see
+    /// `State::set_event_loop_proxy`. `run_on` sets it for the event loop it runs on.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_event_loop_proxy(&mut self, proxy: winit::event_loop::EventLoopProxy<UserEvent>) {
+        for state in self.states.values_mut() {
+            state.set_event_loop_proxy(proxy.clone());
+        }
+        self.proxy = Some(proxy);
+    }
+
+    /// Opens another window with its own `State`. The app exits once all windows are closed.
+    /// On wasm the state is created in the background and gets events once it is ready.
+    pub fn open_window(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        attributes: WindowAttributes,
+    ) -> Result<WindowId, StateError> {
+        // Let winit create a canvas and append it to the document body.
+        #[cfg(target_arch = "wasm32")]
+        let attributes = {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            attributes.with_append(true)
+        };
+        let window = Arc::new(event_loop.create_window(attributes)?);
+        let window_id = window.id();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut state = match &self.gpu {
+                Some(gpu) => State::new_with_gpu(gpu.clone(), window)?,
+                None => State::new(window)?,
+            };
+            self.gpu.get_or_insert_with(|| state.gpu().clone());
+            if let Some(proxy) = &self.proxy {
+                state.set_event_loop_proxy(proxy.clone());
+            }
+            state.set_render_mode(self.render_mode);
+            state.set_control_flow(self.control_flow);
+            self.states.insert(window_id, state);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let pending_states = self.pending_states.clone();
+            let render_mode = self.render_mode;
+            let control_flow = self.control_flow;
+            let gpu = self.gpu.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let state = match gpu {
+                    Some(gpu) => State::new_with_gpu(gpu, window.clone()),
+                    None => State::new_async(window.clone(), StateConfig::default()).await,
+                };
+                let mut state = match state {
+                    Ok(state) => state,
+                    Err(err) => {
+                        log_error!("Failed to create the state: {err}");
+                        return;
+                    }
+                };
+                state.set_render_mode(render_mode);
+                state.set_control_flow(control_flow);
+                // The initial resize event may have been missed while the state was created.
+                state.resize(window.inner_size());
+                pending_states.borrow_mut().push(state);
+                window.request_redraw();
+            });
+        }
+        Ok(window_id)
+    }
+
+    /// Recreates the lost device and the GPU resources of all windows with it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn recover_from_device_loss(&mut self, event_loop: &ActiveEventLoop) {
+        self.gpu = None;
+        let mut states = self.states.values_mut();
+        let Some(first) = states.next() else {
+            return;
+        };
+        if let Err(err) = first.recreate_gpu() {
+            log_error!("Failed to recreate the device: {err}");
+            event_loop.exit();
+            return;
+        }
+        let gpu = first.gpu().clone();
+        for state in states {
+            if let Err(err) = state.replace_gpu(gpu.clone()) {
+                log_error!("Failed to recreate the device: {err}");
+                event_loop.exit();
+                return;
+            }
+        }
+        self.gpu = Some(gpu);
+    }
+
+    /// The state of the window, `None` if it was closed or isn't ready yet.
+    pub fn state_mut(&mut self, window_id: WindowId) -> Option<&mut State> {
+        self.states().get_mut(&window_id)
+    }
+
+    fn states(&mut self) -> &mut HashMap<WindowId, State> {
+        #[cfg(target_arch = "wasm32")]
+        for state in self.pending_states.borrow_mut().drain(..) {
+            self.gpu.get_or_insert_with(|| state.gpu().clone());
+            if let Some(window) = state.window() {
+                self.states.insert(window.id(), state);
+            }
+        }
+        &mut self.states
+    }
+}
+
+impl ApplicationHandler<UserEvent> for WinitWrapper {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.started {
+            self.started = true;
+            if let Err(err) = self.open_window(event_loop, self.window_attributes.clone()) {
+                log_error!("Failed to create the state: {err}");
+                event_loop.exit();
+                return;
+            }
+        }
+        for state in self.states().values_mut() {
+            state.resumed(event_loop);
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        // Closing one window only drops its state, `State` itself would exit the app.
+        if let WindowEvent::CloseRequested = event {
+            self.states().remove(&window_id);
+            if self.states.is_empty() {
+                event_loop.exit();
+            }
+            return;
+        }
+        if let Some(state) = self.states().get_mut(&window_id) {
+            state.window_event(event_loop, window_id, event);
+        }
+    }
+
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        // Until the first `about_to_wait` picks the control flow of the states.
+        if let (StartCause::Init, Some(control_flow)) = (cause, self.control_flow) {
+            event_loop.set_control_flow(control_flow);
+        }
+        for state in self.states().values_mut() {
+            state.new_events(event_loop, cause);
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
+        for state in self.states().values_mut() {
+            state.user_event(event_loop, event.clone());
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        for state in self.states().values_mut() {
+            state.device_event(event_loop, device_id, event.clone());
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // All windows share the device, so they have to get the same new one.
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.gpu.as_ref().is_some_and(|gpu| gpu.is_lost()) {
+            self.recover_from_device_loss(event_loop);
+        }
+        for state in self.states().values_mut() {
+            state.about_to_wait(event_loop);
+        }
+        // Every state sets the control flow for itself, wake up for whichever needs it first.
+        let control_flow =
+            self.states
+                .values()
+                .map(State::control_flow)
+                .fold(ControlFlow::Wait, |a, b| match (a, b) {
+                    (ControlFlow::Poll, _) | (_, ControlFlow::Poll) => ControlFlow::Poll,
+                    (ControlFlow::WaitUntil(a), ControlFlow::WaitUntil(b)) => {
+                        ControlFlow::WaitUntil(a.min(b))
+                    }
+                    (ControlFlow::WaitUntil(a), _) | (_, ControlFlow::WaitUntil(a)) => {
+                        ControlFlow::WaitUntil(a)
+                    }
+                    (ControlFlow::Wait, ControlFlow::Wait) => ControlFlow::Wait,
+                });
+        event_loop.set_control_flow(control_flow);
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        for state in self.states().values_mut() {
+            state.suspended(event_loop);
+        }
+    }
+
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        for state in self.states().values_mut() {
+            state.exiting(event_loop);
+        }
+    }
+
+    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
+        for state in self.states().values_mut() {
+            state.memory_warning(event_loop);
+        }
+    }
+}
+
+/// Creates the event loop, use `EventLoop::create_proxy` on it to send `UserEvent`s from
+/// other threads and then pass it to `run_on`.
+pub fn event_loop() -> Result<EventLoop<UserEvent>, EventLoopError> {
+    let event_loop = EventLoop::with_user_event().build()?;
+    // The states switch to `Poll` for continuous rendering by themselves.
+    event_loop.set_control_flow(ControlFlow::Wait);
+    Ok(event_loop)
+}
+
+/// Creates the event loop and runs `app` on it until it exits, see `run_on`.
+pub fn run<A: ApplicationHandler<UserEvent> + 'static>(app: A) -> Result<(), EventLoopError> {
+    run_on(event_loop()?, app)
+}
+
+/// Runs `app` on an event loop from `event_loop` until it exits. A `WinitWrapper` gets a proxy
+/// of the event loop, so finished `State::spawn_load` jobs wake it up, other apps have to
+/// call `State::set_event_loop_proxy` themselves.
+/// In the browser the event loop keeps running in the background and this returns right away.
+pub fn run_on<A: ApplicationHandler<UserEvent> + 'static>(
+    event_loop: EventLoop<UserEvent>,
+    app: A,
+) -> Result<(), EventLoopError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut app = app;
+        if let Some(wrapper) = (&mut app as &mut dyn Any).downcast_mut::<WinitWrapper>() {
+            wrapper.set_event_loop_proxy(event_loop.create_proxy());
+        }
+        event_loop.run_app(&mut app)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::EventLoopExtWebSys;
+        event_loop.spawn_app(app);
+        Ok(())
+    }
+}
+
+/// Runs a `WinitWrapper` with the default window, see `run`.
+pub fn run_default() -> Result<(), EventLoopError> {
+    run(WinitWrapper::default())
+}
+
+/// Entry point in the browser, build with `wasm-pack build --target web`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen(start)]
+pub fn start() {
+    run_default().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A headless state for the tests that need a device, `None` without an adapter.
+    fn headless_state(width: u32, height: u32) -> Option<State> {
+        match State::new_headless(width, height, wgpu::TextureFormat::Rgba8Unorm) {
+            Ok(state) => Some(state),
+            Err(StateError::NoAdapter) => {
+                eprintln!("No adapter available, skipping the test");
+                None
+            }
+            Err(err) => panic!("Failed to create the headless state: {err}"),
+        }
+    }
+
+    #[test]
+    fn no_surface_formats_is_no_format() {
+        let Some(state) = headless_state(8, 8) else {
+            return;
+        };
+        let surface_caps = wgpu::SurfaceCapabilities {
+            formats: Vec::new(),
+            ..Default::default()
+        };
+        for (prefer_srgb, request_hdr) in [(true, false), (false, false), (true, true)] {
+            assert_eq!(
+                State::choose_surface_format(
+                    &state.gpu.adapter,
+                    &surface_caps,
+                    prefer_srgb,
+                    request_hdr
+                ),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn lost_and_outdated_surfaces_are_reconfigured() {
+        let Some(mut state) = headless_state(8, 8) else {
+            return;
+        };
+        let size = winit::dpi::PhysicalSize::new(12, 10);
+        for err in [wgpu::SurfaceError::Lost, wgpu::SurfaceError::Outdated] {
+            let resizes = state.resizes;
+            assert_eq!(
+                state.recover_from_surface_error(&err, size),
+                SurfaceRecovery::Reconfigure
+            );
+            assert_eq!(state.resizes, resizes + 1);
+            assert_eq!(
+                (state.surface_config.width, state.surface_config.height),
+                (12, 10)
+            );
+        }
+        let resizes = state.resizes;
+        assert_eq!(
+            state.recover_from_surface_error(&wgpu::SurfaceError::Timeout, size),
+            SurfaceRecovery::Retry
+        );
+        assert_eq!(
+            state.recover_from_surface_error(&wgpu::SurfaceError::OutOfMemory, size),
+            SurfaceRecovery::Exit
+        );
+        assert_eq!(state.resizes, resizes);
+    }
+
+    #[test]
+    fn resize_bursts_are_applied_once() {
+        let Some(mut state) = headless_state(8, 8) else {
+            return;
+        };
+        for width in [10, 20, 30] {
+            state.queue_resize(winit::dpi::PhysicalSize::new(width, 16));
+        }
+        assert_eq!(state.resizes, 0);
+        state.apply_pending_resize();
+        assert_eq!(state.resizes, 1);
+        let texture = state.headless_texture().unwrap();
+        assert_eq!((texture.width(), texture.height()), (30, 16));
+        // Nothing is pending anymore.
+        state.apply_pending_resize();
+        assert_eq!(state.resizes, 1);
+    }
+
+    #[test]
+    fn frame_latency_is_clamped_per_backend() {
+        // The backend, its range and the latencies 0, 1, 2, 3 and 20 after clamping.
+        let table = [
+            (wgpu::Backend::Dx12, 1..=16, [1, 1, 2, 3, 16]),
+            (wgpu::Backend::Metal, 1..=2, [1, 1, 2, 2, 2]),
+            (wgpu::Backend::Gl, 2..=2, [2, 2, 2, 2, 2]),
+            (wgpu::Backend::Vulkan, 1..=3, [1, 1, 2, 3, 3]),
+            (wgpu::Backend::BrowserWebGpu, 1..=3, [1, 1, 2, 3, 3]),
+            (wgpu::Backend::Empty, 1..=3, [1, 1, 2, 3, 3]),
+        ];
+        for (backend, range, clamped) in table {
+            assert_eq!(frame_latency_range(backend), range, "{backend:?}");
+            for (latency, expected) in [0, 1, 2, 3, 20].into_iter().zip(clamped) {
+                assert_eq!(
+                    clamp_frame_latency(backend, latency),
+                    expected,
+                    "{backend:?} with {latency}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn default_frame_latency_is_kept_on_every_backend() {
+        for backend in [
+            wgpu::Backend::Empty,
+            wgpu::Backend::Vulkan,
+            wgpu::Backend::Metal,
+            wgpu::Backend::Dx12,
+            wgpu::Backend::Gl,
+            wgpu::Backend::BrowserWebGpu,
+        ] {
+            assert_eq!(clamp_frame_latency(backend, 2), 2, "{backend:?}");
+        }
+    }
+
+    #[test]
+    fn outdated_surfaces_are_reconfigured_like_lost_ones() {
+        assert_eq!(
+            surface_recovery(&wgpu::SurfaceError::Outdated),
+            SurfaceRecovery::Reconfigure
+        );
+        assert_eq!(
+            surface_recovery(&wgpu::SurfaceError::Lost),
+            SurfaceRecovery::Reconfigure
+        );
+    }
+
+    #[test]
+    fn timeouts_are_retried_and_out_of_memory_exits() {
+        assert_eq!(
+            surface_recovery(&wgpu::SurfaceError::Timeout),
+            SurfaceRecovery::Retry
+        );
+        assert_eq!(
+            surface_recovery(&wgpu::SurfaceError::OutOfMemory),
+            SurfaceRecovery::Exit
+        );
+    }
+
+    #[test]
+    fn fifo_is_always_accepted() {
+        use wgpu::PresentMode::{AutoNoVsync, AutoVsync, Fifo, Immediate, Mailbox};
+
+        for modes in [&[][..], &[Fifo], &[Fifo, Mailbox, Immediate]] {
+            assert_eq!(State::choose_present_mode(modes, Fifo), Fifo, "{modes:?}");
+        }
+        // The automatic modes are resolved by wgpu when configuring, never reported.
+        for mode in [AutoVsync, AutoNoVsync] {
+            assert_eq!(State::choose_present_mode(&[Fifo, Immediate], mode), Fifo);
+        }
+    }
+
+    #[test]
+    fn unsupported_present_modes_fall_back_to_fifo() {
+        use wgpu::PresentMode::{Fifo, FifoRelaxed, Immediate, Mailbox};
+
+        let modes = [Fifo, Mailbox];
+        assert_eq!(State::choose_present_mode(&modes, Mailbox), Mailbox);
+        assert_eq!(State::choose_present_mode(&modes, Immediate), Fifo);
+        assert_eq!(State::choose_present_mode(&modes, FifoRelaxed), Fifo);
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use winit::event_loop::EventLoopProxy;
+
+use crate::State;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::UserEvent;
+
+#[cfg(not(target_arch = "wasm32"))]
+type Job = Box<dyn FnOnce() + Send>;
+#[cfg(not(target_arch = "wasm32"))]
+type LoadResult = (u64, Box<dyn Any + Send>);
+#[cfg(not(target_arch = "wasm32"))]
+type DoneCallback = Box<dyn FnOnce(&mut State, Box<dyn Any + Send>)>;
+
+/// Runs the jobs of `State::spawn_load` on a worker thread and collects their results.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct Loader {
+    /// Started with the first job.
+    jobs: Option<Sender<Job>>,
+    results_sender: Sender<LoadResult>,
+    results: Receiver<LoadResult>,
+    /// The `on_done` callbacks of the jobs that didn't finish yet, by job id.
+    pending: HashMap<u64, DoneCallback>,
+    next_id: u64,
+    /// Wakes the event loop when a job finished.
+    proxy: Option<EventLoopProxy<UserEvent>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for Loader {
+    fn default() -> Self {
+        let (results_sender, results) = mpsc::channel();
+        Self {
+            jobs: None,
+            results_sender,
+            results,
+            pending: HashMap::new(),
+            next_id: 0,
+            proxy: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Loader {
+    fn spawn(&mut self, job: Job) {
+        let jobs = self.jobs.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            std::thread::Builder::new()
+                .name("loader".into())
+                .spawn(move || {
+                    // Ends once the state and with it the sender is dropped.
+                    for job in receiver {
+                        job();
+                    }
+                })
+                .expect("failed to spawn the loader thread");
+            sender
+        });
+        // The worker only stops when the sender is dropped, so it's still receiving.
+        let _ = jobs.send(job);
+    }
+}
+
+impl State {
+    /// Runs `job` on a worker thread, e.g. to read and decode an image, and then `on_done`
+    /// with its result on the main thread, e.g. to upload it with `load_texture`. The GPU is
+    /// only touched on the main thread that way. Jobs run one after another in the order they
+    /// were spawned. Without `set_event_loop_proxy`, results are only picked up in
+    /// `about_to_wait` after the next event, the states of a `WinitWrapper` run with `run_on`
+    /// get it. In the browser there are no threads, the job runs right away.
+    pub fn spawn_load<T: Send + 'static>(
+        &mut self,
+        job: impl FnOnce() -> T + Send + 'static,
+        on_done: impl FnOnce(&mut State, T) + 'static,
+    ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let loader = &mut self.loader;
+            let id = loader.next_id;
+            loader.next_id += 1;
+            loader.pending.insert(
+                id,
+                Box::new(move |state, result| {
+                    // The result was boxed by the job of the same id, so it's a `T`.
+                    on_done(state, *result.downcast::<T>().unwrap());
+                }),
+            );
+            let results = loader.results_sender.clone();
+            let proxy = loader.proxy.clone();
+            loader.spawn(Box::new(move || {
+                let result: Box<dyn Any + Send> = Box::new(job());
+                // Only fails if the state is gone, then nobody wants the result anymore.
+                if results.send((id, result)).is_ok() {
+                    if let Some(proxy) = proxy {
+                        let _ = proxy.send_event(UserEvent::LoadFinished);
+                    }
+                }
+            }));
+        }
+        #[cfg(target_arch = "wasm32")]
+        on_done(self, job());
+    }
+
+    /// Lets finished `spawn_load` jobs wake the event loop with `UserEvent::LoadFinished`,
+    /// create it with `EventLoop::create_proxy` on the event loop of `crate::event_loop`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_event_loop_proxy(&mut self, proxy: EventLoopProxy<UserEvent>) {
+        self.loader.proxy = Some(proxy);
+    }
+
+    /// The number of `spawn_load` jobs whose `on_done` wasn't called yet.
+    pub fn pending_loads(&self) -> usize {
+        #[cfg(not(target_arch = "wasm32"))]
+        let pending = self.loader.pending.len();
+        #[cfg(target_arch = "wasm32")]
+        let pending = 0;
+        pending
+    }
+
+    /// Calls `on_done` for the jobs that finished since the last call.
+    pub(crate) fn finish_loads(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        while let Ok((id, result)) = self.loader.results.try_recv() {
+            if let Some(on_done) = self.loader.pending.remove(&id) {
+                on_done(self, result);
+            }
+        }
+    }
+}
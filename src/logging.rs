@@ -0,0 +1,81 @@
+//! Diagnostics through the `log` crate. Without the `log` feature the macros only type-check
+//! their arguments and compile to nothing.
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::error!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::info!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::warn!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
+pub(crate) use {log_debug, log_error, log_info, log_warn};
+
+/// Prints records to stderr, used if the application didn't set up a logger itself.
+#[cfg(feature = "log")]
+struct StderrLogger;
+
+#[cfg(feature = "log")]
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the `StderrLogger` with the level from `RUST_LOG`, `info` by default.
+#[cfg(feature = "log")]
+pub(crate) fn init() {
+    static LOGGER: StderrLogger = StderrLogger;
+    // Fails if there already is a logger, which is then left alone.
+    if log::set_logger(&LOGGER).is_ok() {
+        let level = std::env::var("RUST_LOG")
+            .ok()
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(log::LevelFilter::Info);
+        log::set_max_level(level);
+    }
+}
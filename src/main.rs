@@ -1,267 +1,4 @@
-// MINIMAL WGPU AND WINIT USAGE EXAMPLE
-// Most code is taken from https://sotrh.github.io/learn-wgpu and the winit documentation.
-// I created this because setting up this boilerplate is annoying and got way more annoying with the new winit versions.
-use std::sync::Arc;
-
-use pollster::FutureExt;
-use wgpu::{
-    Adapter, Device, Instance, InstanceDescriptor, MemoryHints, Queue, Surface,
-    SurfaceConfiguration, SurfaceTargetUnsafe,
-};
-use winit::{
-    application::ApplicationHandler,
-    event::{DeviceEvent, DeviceId, StartCause, WindowEvent},
-    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    window::{Window, WindowAttributes, WindowId},
-};
-
-/// The main struct that holds the state of the application.
-/// Use this struct to hold the state of the application.
-struct State {
-    // WGPU STUFF
-    instance: Instance,
-    surface: Surface<'static>,
-    adapter: Adapter,
-    device: Device,
-    queue: Queue,
-    surface_config: SurfaceConfiguration,
-
-    // Last because it needs to be dropped after the surface.
-    window: Arc<Window>,
-}
-
-impl State {
-    fn new(window: Arc<Window>) -> Self {
-        let size = window.inner_size();
-        // WGPU STUFF, NOTE: WGPU settings do not take wasm into account
-        let instance = Instance::new(InstanceDescriptor::default());
-        // NOTE: Surface is created unsafe, make sure surface is destroyed before window.
-        let surface = unsafe {
-            instance
-                .create_surface_unsafe(SurfaceTargetUnsafe::from_window(&window).unwrap())
-                .unwrap()
-        };
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .block_on()
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    label: None,
-                    memory_hints: MemoryHints::Performance,
-                },
-                None, // Trace path
-            )
-            .block_on()
-            .unwrap();
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
-        let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-
-        Self {
-            instance,
-            surface,
-            adapter,
-            device,
-            queue,
-            surface_config,
-            window,
-        }
-    }
-
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.surface_config.width = new_size.width;
-            self.surface_config.height = new_size.height;
-            self.surface.configure(&self.device, &self.surface_config);
-        }
-    }
-
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
-        {
-            let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-        }
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-
-        Ok(())
-    }
-}
-
-/// We only implement the `ApplicationHandler` trait for the `State` struct so that we can
-/// handle the events that are sent to the application in the `State` struct implementations.
-/// This is so we can access documentation in our code editors on the trait methods directly.
-impl ApplicationHandler for State {
-    fn resumed(&mut self, _event_loop: &ActiveEventLoop) {}
-
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
-        event: WindowEvent,
-    ) {
-        match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
-            WindowEvent::Resized(_) => {
-                let size = self.window.inner_size();
-                self.resize(size);
-            }
-            WindowEvent::RedrawRequested => {
-                match self.render() {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => self.resize(self.window.inner_size()),
-                    Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-                    _ => {}
-                }
-                self.window.request_redraw();
-            }
-            _ => {}
-        }
-    }
-
-    fn new_events(&mut self, _event_loop: &ActiveEventLoop, _cause: StartCause) {}
-
-    fn device_event(
-        &mut self,
-        _event_loop: &ActiveEventLoop,
-        _device_id: DeviceId,
-        _event: DeviceEvent,
-    ) {
-    }
-
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {}
-
-    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {}
-
-    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {}
-
-    fn memory_warning(&mut self, _event_loop: &ActiveEventLoop) {}
-}
-
-#[derive(Default)]
-struct WinitWrapper {
-    window: Option<Arc<Window>>,
-    state: Option<State>,
-}
-
-impl ApplicationHandler for WinitWrapper {
-    fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        if self.window.is_none() {
-            let window = Arc::new(
-                event_loop
-                    .create_window(WindowAttributes::default())
-                    .unwrap(),
-            );
-            self.window = Some(window.clone());
-            self.state = Some(State::new(window));
-        }
-        self.state.as_mut().unwrap().resumed(event_loop);
-    }
-
-    fn window_event(
-        &mut self,
-        event_loop: &winit::event_loop::ActiveEventLoop,
-        window_id: winit::window::WindowId,
-        event: winit::event::WindowEvent,
-    ) {
-        if let Some(state) = self.state.as_mut() {
-            state.window_event(event_loop, window_id, event);
-        }
-    }
-
-    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
-        if let Some(state) = self.state.as_mut() {
-            state.new_events(event_loop, cause);
-        }
-    }
-
-    fn device_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        device_id: DeviceId,
-        event: DeviceEvent,
-    ) {
-        if let Some(state) = self.state.as_mut() {
-            state.device_event(event_loop, device_id, event);
-        }
-    }
-
-    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        if let Some(state) = self.state.as_mut() {
-            state.about_to_wait(event_loop);
-        }
-    }
-
-    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
-        if let Some(state) = self.state.as_mut() {
-            state.suspended(event_loop);
-        }
-    }
-
-    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
-        if let Some(state) = self.state.as_mut() {
-            state.exiting(event_loop);
-        }
-    }
-
-    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
-        if let Some(state) = self.state.as_mut() {
-            state.memory_warning(event_loop);
-        }
-    }
-}
-
-fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Wait);
-    let mut winit_wrapper = WinitWrapper::default();
-    event_loop.run_app(&mut winit_wrapper).unwrap();
+fn main() -> Result<(), winit_wgpu_min::Error> {
+    winit_wgpu_min::run_default()?;
+    Ok(())
 }
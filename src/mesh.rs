@@ -0,0 +1,96 @@
+use wgpu::util::DeviceExt;
+
+use crate::{
+    instance::{InstanceRaw, Instances},
+    pipeline::{self, TargetFormats},
+};
+
+/// A vertex as it is laid out in the vertex buffer, see `Vertex::LAYOUT`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+}
+
+// SAFETY: `Vertex` is `repr(C)`, only contains `f32`s and has no padding.
+unsafe impl bytemuck::Zeroable for Vertex {}
+unsafe impl bytemuck::Pod for Vertex {}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    /// Matches the `VertexInput` of `shaders/mesh.wgsl`.
+    pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &Self::ATTRIBUTES,
+    };
+}
+
+/// Vertex and index buffers of a mesh uploaded with `State::upload_mesh`.
+pub(crate) struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+impl Mesh {
+    /// Returns `None` if there is nothing to draw.
+    pub fn new(device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> Option<Self> {
+        if vertices.is_empty() || indices.is_empty() {
+            return None;
+        }
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Some(Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+        })
+    }
+
+    /// Draws one copy of the mesh per instance.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, instances: &Instances) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instances.buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..instances.count);
+    }
+}
+
+pub(crate) fn create_pipeline(
+    device: &wgpu::Device,
+    camera_layout: &wgpu::BindGroupLayout,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/mesh.wgsl"));
+    create_pipeline_with_shader(device, &shader, camera_layout, targets)
+}
+
+/// Like `create_pipeline` with another shader, which needs the inputs and bindings of
+/// `shaders/mesh.wgsl`.
+pub(crate) fn create_pipeline_with_shader(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    camera_layout: &wgpu::BindGroupLayout,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    pipeline::create_render_pipeline(
+        device,
+        "Mesh Pipeline",
+        shader,
+        &[camera_layout],
+        &[Vertex::LAYOUT, InstanceRaw::LAYOUT],
+        targets,
+    )
+}
@@ -0,0 +1,134 @@
+/// The number of levels of a full mip chain, every level halves the size and rounds down
+/// until both sides are 1.
+pub(crate) fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Fills mip levels 1 and up of `texture` by drawing each level from the previous one with
+/// linear filtering, wgpu has no built-in mipmap generation. The texture needs the
+/// `RENDER_ATTACHMENT` and `TEXTURE_BINDING` usages.
+pub(crate) fn generate_mipmaps(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) {
+    if texture.mip_level_count() < 2 {
+        return;
+    }
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/blit.wgsl"));
+    // The layout is derived from the shader since the pipeline is only used here.
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Mipmap Pipeline"),
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(texture.format().into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Mipmap Sampler"),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+    let bind_group_layout = pipeline.get_bind_group_layout(0);
+    let level_view = |level| {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Mip Level View"),
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    };
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Mipmap Encoder"),
+    });
+    for level in 1..texture.mip_level_count() {
+        let source = level_view(level - 1);
+        let target = level_view(level);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mipmap Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Mipmap Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_pixel_has_one_level() {
+        assert_eq!(mip_level_count(1, 1), 1);
+    }
+
+    #[test]
+    fn zero_size_counts_as_one_pixel() {
+        assert_eq!(mip_level_count(0, 0), 1);
+    }
+
+    #[test]
+    fn power_of_two_sizes_halve_down_to_one_pixel() {
+        // 256, 128, 64, 32, 16, 8, 4, 2, 1.
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(2, 2), 2);
+    }
+
+    #[test]
+    fn non_power_of_two_sizes_round_down() {
+        // 300, 150, 75, 37, 18, 9, 4, 2, 1.
+        assert_eq!(mip_level_count(300, 300), 9);
+        // 255, 127, 63, 31, 15, 7, 3, 1.
+        assert_eq!(mip_level_count(255, 255), 8);
+        assert_eq!(mip_level_count(3, 3), 2);
+    }
+
+    #[test]
+    fn non_square_sizes_follow_the_longer_side() {
+        assert_eq!(mip_level_count(1024, 1), 11);
+        assert_eq!(mip_level_count(1, 1024), 11);
+        assert_eq!(mip_level_count(640, 480), 10);
+    }
+}
@@ -0,0 +1,93 @@
+use wgpu::rwh::{HasDisplayHandle, RawDisplayHandle};
+use winit::{
+    monitor::{MonitorHandle, VideoModeHandle},
+    window::{Fullscreen, Window},
+};
+
+use crate::{logging::log_warn, State};
+
+impl State {
+    /// The monitors of the system, e.g. for a fullscreen settings menu. Empty in headless mode.
+    pub fn available_monitors(&self) -> Vec<MonitorHandle> {
+        self.window
+            .as_ref()
+            .map(|window| window.available_monitors().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every monitor with the video modes it supports, each with its size, bit depth and
+    /// refresh rate. Empty in headless mode.
+    pub fn monitor_video_modes(&self) -> Vec<(MonitorHandle, Vec<VideoModeHandle>)> {
+        self.available_monitors()
+            .into_iter()
+            .map(|monitor| {
+                let video_modes = monitor.video_modes().collect();
+                (monitor, video_modes)
+            })
+            .collect()
+    }
+
+    /// The video modes of the monitor the window is on. Empty in headless mode or if the
+    /// monitor can't be queried.
+    pub fn current_video_modes(&self) -> Vec<VideoModeHandle> {
+        self.window
+            .as_ref()
+            .and_then(|window| window.current_monitor())
+            .map(|monitor| monitor.video_modes().collect())
+            .unwrap_or_default()
+    }
+
+    /// Switches between windowed and exclusive fullscreen with `mode`, which changes the
+    /// resolution and refresh rate of its monitor. See `toggle_fullscreen` for borderless.
+    pub fn toggle_exclusive_fullscreen(&mut self, mode: VideoModeHandle) {
+        if self.fullscreen {
+            self.exit_fullscreen();
+        } else {
+            self.set_exclusive_fullscreen(mode);
+        }
+    }
+
+    /// Enters exclusive fullscreen with `mode`, one of `monitor_video_modes`. The surface is
+    /// reconfigured to the mode's size by the `Resized` event that follows. Exclusive mode is
+    /// supported on Windows, macOS and X11, elsewhere (Wayland, the web, Android and iOS) this
+    /// falls back to borderless fullscreen on the mode's monitor.
+    pub fn set_exclusive_fullscreen(&mut self, mode: VideoModeHandle) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if !self.fullscreen {
+            self.windowed_size = Some(window.inner_size());
+        }
+        self.fullscreen = true;
+        if supports_exclusive_fullscreen(window) {
+            window.set_fullscreen(Some(Fullscreen::Exclusive(mode)));
+        } else {
+            log_warn!("Exclusive fullscreen isn't supported, using borderless");
+            window.set_fullscreen(Some(Fullscreen::Borderless(Some(mode.monitor()))));
+        }
+    }
+
+    /// Leaves either kind of fullscreen. After exclusive fullscreen the window gets back the
+    /// size it had before, since some platforms keep the size of the video mode.
+    pub fn exit_fullscreen(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        self.fullscreen = false;
+        window.set_fullscreen(None);
+        if let Some(size) = self.windowed_size.take() {
+            let _ = window.request_inner_size(size);
+        }
+    }
+}
+
+/// Whether the windowing system lets applications change the video mode.
+fn supports_exclusive_fullscreen(window: &Window) -> bool {
+    !matches!(
+        window.display_handle().map(|handle| handle.as_raw()),
+        Ok(RawDisplayHandle::Wayland(_)
+            | RawDisplayHandle::Web(_)
+            | RawDisplayHandle::Android(_)
+            | RawDisplayHandle::UiKit(_))
+    )
+}
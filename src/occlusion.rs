@@ -0,0 +1,147 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::State;
+
+/// The number of frames whose query results can be in flight at once.
+const SLOT_COUNT: usize = 3;
+
+/// A readback buffer for the query results of one frame.
+struct Slot {
+    buffer: wgpu::Buffer,
+    /// Whether the results were copied into the buffer and not read back yet.
+    in_flight: bool,
+    /// Set by the `map_async` callback.
+    mapped: Arc<AtomicBool>,
+}
+
+/// Counts the samples that pass the depth test between `begin_occlusion_query` and
+/// `end_occlusion_query` in the main render pass and the shadow passes. The results are read
+/// back without blocking, so they are a few frames old.
+pub(crate) struct OcclusionQueries {
+    pub query_set: wgpu::QuerySet,
+    count: u32,
+    resolve_buffer: wgpu::Buffer,
+    slots: Vec<Slot>,
+    results: Vec<u64>,
+}
+
+impl OcclusionQueries {
+    pub fn new(device: &wgpu::Device, count: u32) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Occlusion Query Set"),
+            ty: wgpu::QueryType::Occlusion,
+            count,
+        });
+        let size = count as u64 * wgpu::QUERY_SIZE as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let slots = (0..SLOT_COUNT)
+            .map(|_| Slot {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Occlusion Readback Buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                in_flight: false,
+                mapped: Arc::new(AtomicBool::new(false)),
+            })
+            .collect();
+        Self {
+            query_set,
+            count,
+            resolve_buffer,
+            slots,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn results(&self) -> &[u64] {
+        &self.results
+    }
+
+    /// Reads back the results of finished frames.
+    pub fn collect(&mut self, device: &wgpu::Device) {
+        // Native backends only call the map callbacks while the device is polled.
+        device.poll(wgpu::Maintain::Poll);
+        for slot in &mut self.slots {
+            if !slot.in_flight || !slot.mapped.load(Ordering::Acquire) {
+                continue;
+            }
+            {
+                let data = slot.buffer.slice(..).get_mapped_range();
+                self.results.clear();
+                self.results.extend_from_slice(bytemuck::cast_slice(&data));
+            }
+            slot.buffer.unmap();
+            slot.in_flight = false;
+            slot.mapped.store(false, Ordering::Release);
+        }
+    }
+
+    /// Resolves the results of this frame into a free readback buffer and returns its index
+    /// for `map`, `None` if all buffers are still in flight and the frame is skipped.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) -> Option<usize> {
+        let index = self.slots.iter().position(|slot| !slot.in_flight)?;
+        encoder.resolve_query_set(&self.query_set, 0..self.count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.slots[index].buffer,
+            0,
+            self.resolve_buffer.size(),
+        );
+        self.slots[index].in_flight = true;
+        Some(index)
+    }
+
+    /// Starts mapping the buffer returned by `resolve`, call it after the frame was submitted.
+    pub fn map(&self, index: usize) {
+        let mapped = self.slots[index].mapped.clone();
+        self.slots[index]
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if result.is_ok() {
+                    mapped.store(true, Ordering::Release);
+                }
+            });
+    }
+}
+
+impl State {
+    /// Creates `count` occlusion queries, 0 removes them. Wrap draws in
+    /// `RenderPass::begin_occlusion_query(index)` and `end_occlusion_query` to count how many
+    /// of their samples pass the depth test, e.g. to skip objects that are hidden. Use every
+    /// query each frame, all of them are resolved and the GL backend reports unused ones as
+    /// errors.
+    pub fn set_occlusion_query_count(&mut self, count: u32) {
+        // wgpu doesn't allow more queries in a set.
+        let count = count.min(wgpu::QUERY_SET_MAX_QUERIES);
+        self.occlusion_queries =
+            (count > 0).then(|| OcclusionQueries::new(&self.gpu.device, count));
+    }
+
+    /// The passed samples of every occlusion query in a recent frame, a few frames behind
+    /// because the results are read back without blocking. Empty until the first results
+    /// arrive. Queries that weren't used in that frame have no meaningful result.
+    pub fn occlusion_results(&self) -> &[u64] {
+        self.occlusion_queries
+            .as_ref()
+            .map_or(&[], |occlusion_queries| occlusion_queries.results())
+    }
+
+    /// The query set for `RenderPassDescriptor::occlusion_query_set`, `None` without queries.
+    pub(crate) fn occlusion_query_set(&self) -> Option<&wgpu::QuerySet> {
+        self.occlusion_queries
+            .as_ref()
+            .map(|occlusion_queries| &occlusion_queries.query_set)
+    }
+}
@@ -0,0 +1,573 @@
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+use crate::State;
+
+/// Where a window is, to reopen it there on the next launch, see `State::window_placement`.
+/// Serializable with the `serde` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowPlacement {
+    /// Of the top left corner of the window frame. `None` where windows can't query their
+    /// position, e.g. on Wayland.
+    pub position: Option<PhysicalPosition<i32>>,
+    /// Of the window's content, without the frame.
+    pub size: PhysicalSize<u32>,
+    pub maximized: bool,
+}
+
+impl State {
+    /// The position of the window frame's top left corner on the desktop, kept up to date by
+    /// `Moved` events. `None` in headless mode and where windows can't query their position,
+    /// e.g. on Wayland and the web.
+    pub fn window_position(&self) -> Option<PhysicalPosition<i32>> {
+        self.window_position
+            .or_else(|| self.window.as_ref()?.outer_position().ok())
+    }
+
+    /// Moves the window frame's top left corner to `position`. Ignored where windows can't be
+    /// moved by the application, e.g. on Wayland.
+    pub fn set_window_position(&self, position: PhysicalPosition<i32>) {
+        if let Some(window) = &self.window {
+            window.set_outer_position(position);
+        }
+    }
+
+    /// The placement of the window to save when the app exits, `None` in headless mode.
+    pub fn window_placement(&self) -> Option<WindowPlacement> {
+        let window = self.window.as_ref()?;
+        Some(WindowPlacement {
+            position: self.window_position(),
+            size: window.inner_size(),
+            maximized: window.is_maximized(),
+        })
+    }
+
+    /// Moves and resizes the window to a saved placement. A position that isn't on any
+    /// monitor anymore, e.g. after unplugging one, is moved onto the closest monitor so the
+    /// window doesn't open off-screen.
+    pub fn restore_window_placement(&self, placement: WindowPlacement) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        // The size first, so the window gets it back when it's unmaximized.
+        let _ = window.request_inner_size(placement.size);
+        if let Some(position) = placement.position {
+            let monitors: Vec<_> = self
+                .available_monitors()
+                .into_iter()
+                .map(|monitor| (monitor.position(), monitor.size()))
+                .collect();
+            window.set_outer_position(clamp_to_monitors(position, window.outer_size(), &monitors));
+        }
+        window.set_maximized(placement.maximized);
+    }
+}
+
+/// Moves a window of `size` at `position` onto the closest of the monitors, given by their
+/// position and size, if it doesn't overlap any. It's kept inside that monitor as far as it
+/// fits.
+fn clamp_to_monitors(
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    monitors: &[(PhysicalPosition<i32>, PhysicalSize<u32>)],
+) -> PhysicalPosition<i32> {
+    let (right, bottom) = (
+        position.x + size.width as i32,
+        position.y + size.height as i32,
+    );
+    let visible = monitors.iter().any(|(origin, area)| {
+        position.x < origin.x + area.width as i32
+            && right > origin.x
+            && position.y < origin.y + area.height as i32
+            && bottom > origin.y
+    });
+    if visible {
+        return position;
+    }
+    // Measured between the monitor and window centers.
+    let distance = |(origin, area): &(PhysicalPosition<i32>, PhysicalSize<u32>)| {
+        let dx = (origin.x + area.width as i32 / 2) as i64 - (position.x + right) as i64 / 2;
+        let dy = (origin.y + area.height as i32 / 2) as i64 - (position.y + bottom) as i64 / 2;
+        dx * dx + dy * dy
+    };
+    let Some((origin, area)) = monitors.iter().min_by_key(|monitor| distance(monitor)) else {
+        return position;
+    };
+    // The top left corner stays on the monitor if the window is larger, so the title bar
+    // can be reached.
+    PhysicalPosition::new(
+        position
+            .x
+            .min(origin.x + area.width as i32 - size.width as i32)
+            .max(origin.x),
+        position
+            .y
+            .min(origin.y + area.height as i32 - size.height as i32)
+            .max(origin.y),
+    )
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{
+        de::{self, MapAccess, SeqAccess, Visitor},
+        ser::SerializeStruct,
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+    use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+    use super::WindowPlacement;
+
+    const FIELDS: &[&str] = &["position", "size", "maximized"];
+
+    // Written by hand, the position and size are stored as `[x, y]` and `[width, height]`.
+    impl Serialize for WindowPlacement {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut placement = serializer.serialize_struct("WindowPlacement", 3)?;
+            placement.serialize_field(
+                "position",
+                &self.position.map(|position| [position.x, position.y]),
+            )?;
+            placement.serialize_field("size", &[self.size.width, self.size.height])?;
+            placement.serialize_field("maximized", &self.maximized)?;
+            placement.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for WindowPlacement {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_struct("WindowPlacement", FIELDS, PlacementVisitor)
+        }
+    }
+
+    struct PlacementVisitor;
+
+    impl PlacementVisitor {
+        fn placement(
+            position: Option<[i32; 2]>,
+            size: [u32; 2],
+            maximized: bool,
+        ) -> WindowPlacement {
+            WindowPlacement {
+                position: position.map(|[x, y]| PhysicalPosition::new(x, y)),
+                size: PhysicalSize::new(size[0], size[1]),
+                maximized,
+            }
+        }
+    }
+
+    impl<'de> Visitor<'de> for PlacementVisitor {
+        type Value = WindowPlacement;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a window placement")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let position = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let size = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            let maximized = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+            Ok(Self::placement(position, size, maximized))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut position = None;
+            let mut size = None;
+            let mut maximized = None;
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "position" => position = Some(map.next_value()?),
+                    "size" => size = Some(map.next_value()?),
+                    "maximized" => maximized = Some(map.next_value()?),
+                    _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                }
+            }
+            Ok(Self::placement(
+                position.unwrap_or_default(),
+                size.ok_or_else(|| de::Error::missing_field("size"))?,
+                maximized.unwrap_or_default(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two monitors side by side, the right one larger.
+    const MONITORS: [(PhysicalPosition<i32>, PhysicalSize<u32>); 2] = [
+        (PhysicalPosition::new(0, 0), PhysicalSize::new(1920, 1080)),
+        (
+            PhysicalPosition::new(1920, 0),
+            PhysicalSize::new(2560, 1440),
+        ),
+    ];
+    const SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+    fn clamp(x: i32, y: i32, size: PhysicalSize<u32>) -> (i32, i32) {
+        let position = clamp_to_monitors(PhysicalPosition::new(x, y), size, &MONITORS);
+        (position.x, position.y)
+    }
+
+    #[test]
+    fn windows_on_a_monitor_stay() {
+        assert_eq!(clamp(100, 100, SIZE), (100, 100));
+        assert_eq!(clamp(3000, 800, SIZE), (3000, 800));
+        // Across both monitors.
+        assert_eq!(clamp(1500, 200, SIZE), (1500, 200));
+    }
+
+    #[test]
+    fn partly_visible_windows_stay() {
+        assert_eq!(clamp(-700, 100, SIZE), (-700, 100));
+        assert_eq!(clamp(4400, 1400, SIZE), (4400, 1400));
+    }
+
+    #[test]
+    fn windows_off_every_monitor_move_onto_the_closest() {
+        assert_eq!(clamp(-3000, 500, SIZE), (0, 480));
+        assert_eq!(clamp(6000, 200, SIZE), (3680, 200));
+        // Below the right monitor, which reaches further down.
+        assert_eq!(clamp(2000, 3000, SIZE), (2000, 840));
+        assert_eq!(clamp(100, -2000, SIZE), (100, 0));
+    }
+
+    #[test]
+    fn windows_larger_than_the_monitor_keep_their_top_left_corner_on_it() {
+        assert_eq!(clamp(-5000, -5000, PhysicalSize::new(3000, 2000)), (0, 0));
+    }
+
+    #[test]
+    fn without_monitors_the_position_is_kept() {
+        let position = PhysicalPosition::new(-5000, 9000);
+        assert_eq!(clamp_to_monitors(position, SIZE, &[]), position);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use serde::{
+        de::{self, value::Error, IntoDeserializer, Visitor},
+        ser::{self, Impossible},
+        Deserialize, Serialize,
+    };
+    use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+    use super::WindowPlacement;
+
+    /// Serialized data, the subset of serde's data model `WindowPlacement` uses.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Value {
+        Bool(bool),
+        I64(i64),
+        U64(u64),
+        Option(Option<Box<Value>>),
+        Seq(Vec<Value>),
+        Map(Vec<(String, Value)>),
+    }
+
+    fn unsupported<T>() -> Result<T, Error> {
+        Err(ser::Error::custom("unsupported"))
+    }
+
+    struct ValueSerializer;
+
+    struct SeqSerializer(Vec<Value>);
+
+    struct StructSerializer(Vec<(String, Value)>);
+
+    impl ser::Serializer for ValueSerializer {
+        type Ok = Value;
+        type Error = Error;
+        type SerializeSeq = SeqSerializer;
+        type SerializeTuple = SeqSerializer;
+        type SerializeTupleStruct = Impossible<Value, Error>;
+        type SerializeTupleVariant = Impossible<Value, Error>;
+        type SerializeMap = Impossible<Value, Error>;
+        type SerializeStruct = StructSerializer;
+        type SerializeStructVariant = Impossible<Value, Error>;
+
+        fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+            Ok(Value::Bool(v))
+        }
+        fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+            Ok(Value::I64(v.into()))
+        }
+        fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+            Ok(Value::I64(v.into()))
+        }
+        fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+            Ok(Value::I64(v.into()))
+        }
+        fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+            Ok(Value::I64(v))
+        }
+        fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+            Ok(Value::U64(v.into()))
+        }
+        fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+            Ok(Value::U64(v.into()))
+        }
+        fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+            Ok(Value::U64(v.into()))
+        }
+        fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+            Ok(Value::U64(v))
+        }
+        fn serialize_f32(self, _: f32) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_f64(self, _: f64) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_char(self, _: char) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_str(self, _: &str) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_bytes(self, _: &[u8]) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_none(self) -> Result<Value, Error> {
+            Ok(Value::Option(None))
+        }
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+            Ok(Value::Option(Some(Box::new(value.serialize(self)?))))
+        }
+        fn serialize_unit(self) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_unit_struct(self, _: &'static str) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_unit_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+        ) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: &T,
+        ) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<Value, Error> {
+            unsupported()
+        }
+        fn serialize_seq(self, _: Option<usize>) -> Result<SeqSerializer, Error> {
+            Ok(SeqSerializer(Vec::new()))
+        }
+        fn serialize_tuple(self, _: usize) -> Result<SeqSerializer, Error> {
+            Ok(SeqSerializer(Vec::new()))
+        }
+        fn serialize_tuple_struct(
+            self,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleStruct, Error> {
+            unsupported()
+        }
+        fn serialize_tuple_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            unsupported()
+        }
+        fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+            unsupported()
+        }
+        fn serialize_struct(self, _: &'static str, _: usize) -> Result<StructSerializer, Error> {
+            Ok(StructSerializer(Vec::new()))
+        }
+        fn serialize_struct_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            unsupported()
+        }
+    }
+
+    impl ser::SerializeSeq for SeqSerializer {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            self.0.push(value.serialize(ValueSerializer)?);
+            Ok(())
+        }
+        fn end(self) -> Result<Value, Error> {
+            Ok(Value::Seq(self.0))
+        }
+    }
+
+    impl ser::SerializeTuple for SeqSerializer {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            ser::SerializeSeq::serialize_element(self, value)
+        }
+        fn end(self) -> Result<Value, Error> {
+            ser::SerializeSeq::end(self)
+        }
+    }
+
+    impl ser::SerializeStruct for StructSerializer {
+        type Ok = Value;
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(
+            &mut self,
+            key: &'static str,
+            value: &T,
+        ) -> Result<(), Error> {
+            self.0
+                .push((key.to_string(), value.serialize(ValueSerializer)?));
+            Ok(())
+        }
+        fn end(self) -> Result<Value, Error> {
+            Ok(Value::Map(self.0))
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Value {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self {
+                Value::Bool(v) => visitor.visit_bool(v),
+                Value::I64(v) => visitor.visit_i64(v),
+                Value::U64(v) => visitor.visit_u64(v),
+                Value::Option(None) => visitor.visit_none(),
+                Value::Option(Some(value)) => visitor.visit_some(*value),
+                Value::Seq(values) => {
+                    visitor.visit_seq(de::value::SeqDeserializer::new(values.into_iter()))
+                }
+                Value::Map(entries) => {
+                    visitor.visit_map(de::value::MapDeserializer::new(entries.into_iter()))
+                }
+            }
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map struct
+            enum identifier ignored_any
+        }
+    }
+
+    impl IntoDeserializer<'_, Error> for Value {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    fn map(entries: &[(&str, Value)]) -> Value {
+        Value::Map(
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    fn pair_i64(a: i64, b: i64) -> Value {
+        Value::Seq(vec![Value::I64(a), Value::I64(b)])
+    }
+
+    fn pair_u64(a: u64, b: u64) -> Value {
+        Value::Seq(vec![Value::U64(a), Value::U64(b)])
+    }
+
+    const PLACEMENT: WindowPlacement = WindowPlacement {
+        position: Some(PhysicalPosition::new(-10, 20)),
+        size: PhysicalSize::new(800, 600),
+        maximized: true,
+    };
+
+    #[test]
+    fn placement_is_serialized_with_arrays() {
+        assert_eq!(
+            PLACEMENT.serialize(ValueSerializer).unwrap(),
+            map(&[
+                ("position", Value::Option(Some(Box::new(pair_i64(-10, 20))))),
+                ("size", pair_u64(800, 600)),
+                ("maximized", Value::Bool(true)),
+            ])
+        );
+    }
+
+    #[test]
+    fn placement_round_trips() {
+        for placement in [
+            PLACEMENT,
+            WindowPlacement {
+                position: None,
+                maximized: false,
+                ..PLACEMENT
+            },
+        ] {
+            let value = placement.serialize(ValueSerializer).unwrap();
+            assert_eq!(WindowPlacement::deserialize(value).unwrap(), placement);
+        }
+    }
+
+    #[test]
+    fn placement_deserializes_from_a_sequence() {
+        let value = Value::Seq(vec![
+            Value::Option(Some(Box::new(pair_i64(-10, 20)))),
+            pair_u64(800, 600),
+            Value::Bool(true),
+        ]);
+        assert_eq!(WindowPlacement::deserialize(value).unwrap(), PLACEMENT);
+    }
+
+    #[test]
+    fn only_the_size_is_required() {
+        let value = map(&[("size", pair_u64(800, 600))]);
+        assert_eq!(
+            WindowPlacement::deserialize(value).unwrap(),
+            WindowPlacement {
+                position: None,
+                size: PhysicalSize::new(800, 600),
+                maximized: false,
+            }
+        );
+        let error = WindowPlacement::deserialize(map(&[("maximized", Value::Bool(true))]));
+        assert_eq!(error.unwrap_err().to_string(), "missing field `size`");
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let value = map(&[("size", pair_u64(800, 600)), ("monitor", Value::U64(1))]);
+        assert!(WindowPlacement::deserialize(value)
+            .unwrap_err()
+            .to_string()
+            .starts_with("unknown field `monitor`"));
+    }
+}
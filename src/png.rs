@@ -0,0 +1,192 @@
+//! A tiny PNG encoder for screenshots. The image data is stored uncompressed,
+//! which keeps the encoder small at the cost of larger files.
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// The maximum length of a stored deflate block.
+const MAX_BLOCK_LEN: usize = u16::MAX as usize;
+
+/// Encodes tightly packed 8 bit RGBA pixels as a PNG.
+pub(crate) fn encode_rgba(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    assert_eq!(rgba.len(), width as usize * height as usize * 4);
+
+    // Every row starts with its filter type, 0 means no filter.
+    let row_len = width as usize * 4;
+    let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+    for row in rgba.chunks_exact(row_len.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, color type 6 (RGBA), default compression, filter and no interlacing.
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK_LEN).peekable();
+    if blocks.peek().is_none() {
+        // An empty final block.
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The chunks of `png` as kind and data, checking every CRC.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        assert_eq!(png[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let kind_and_data = &rest[4..8 + len];
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(kind_and_data));
+            chunks.push((kind_and_data[..4].try_into().unwrap(), &kind_and_data[4..]));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    /// Unwraps a zlib stream of stored blocks, checking the headers and the checksum.
+    fn unzlib_stored(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(zlib[..2], [0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut rest = &zlib[2..];
+        loop {
+            let is_final = rest[0] == 1;
+            let len = u16::from_le_bytes([rest[1], rest[2]]);
+            assert_eq!(u16::from_le_bytes([rest[3], rest[4]]), !len);
+            data.extend_from_slice(&rest[5..5 + len as usize]);
+            rest = &rest[5 + len as usize..];
+            if is_final {
+                break;
+            }
+        }
+        assert_eq!(rest, adler32(&data).to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn crc32_matches_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        // The CRC every PNG ends with.
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn adler32_matches_known_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        // Both sums wrap around the modulus.
+        assert_eq!(adler32(&[0xff; 6000]), 0xa497_59ea);
+    }
+
+    #[test]
+    fn one_pixel_png_has_the_exact_bytes() {
+        let png = encode_rgba(1, 1, &[1, 2, 3, 4]);
+        let mut expected = SIGNATURE.to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 13]);
+        expected.extend_from_slice(b"IHDR");
+        expected.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+        expected.extend_from_slice(&crc32(&expected[12..29]).to_be_bytes());
+        // The filter byte and the pixel in one final stored block.
+        let idat = [
+            0x78, 0x01, 1, 5, 0, 0xfa, 0xff, 0, 1, 2, 3, 4, 0x00, 0x19, 0x00, 0x0b,
+        ];
+        expected.extend_from_slice(&(idat.len() as u32).to_be_bytes());
+        expected.extend_from_slice(b"IDAT");
+        expected.extend_from_slice(&idat);
+        let idat_start = expected.len() - idat.len() - 4;
+        expected.extend_from_slice(&crc32(&expected[idat_start..]).to_be_bytes());
+        expected.extend_from_slice(&[0, 0, 0, 0]);
+        expected.extend_from_slice(b"IEND");
+        expected.extend_from_slice(&[0xae, 0x42, 0x60, 0x82]);
+        assert_eq!(png, expected);
+    }
+
+    #[test]
+    fn rows_round_trip_through_several_blocks() {
+        // More than one stored block of image data.
+        let (width, height) = (300, 80);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i % 251) as u8).collect();
+        let png = encode_rgba(width, height, &rgba);
+        let chunks = chunks(&png);
+        let kinds: Vec<_> = chunks.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1[..8], [0, 0, 1, 44, 0, 0, 0, 80]);
+
+        let raw = unzlib_stored(chunks[1].1);
+        assert!(raw.len() > MAX_BLOCK_LEN);
+        let row_len = width as usize * 4;
+        let mut pixels = Vec::new();
+        for row in raw.chunks_exact(row_len + 1) {
+            assert_eq!(row[0], 0);
+            pixels.extend_from_slice(&row[1..]);
+        }
+        assert_eq!(pixels, rgba);
+    }
+
+    #[test]
+    fn empty_image_has_an_empty_final_block() {
+        let png = encode_rgba(0, 0, &[]);
+        let chunks = chunks(&png);
+        assert_eq!(unzlib_stored(chunks[1].1), []);
+    }
+}
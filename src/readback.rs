@@ -0,0 +1,84 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+/// Resolves once the `map_async` callback was called.
+struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Maps `slice` for reading and waits until the mapping is done.
+/// Native backends only call the map callback while the device is polled, so this blocks
+/// on `Maintain::Wait` there. On the web the future resolves when the browser is done.
+pub(crate) async fn map_read(
+    device: &wgpu::Device,
+    slice: wgpu::BufferSlice<'_>,
+) -> Result<(), wgpu::BufferAsyncError> {
+    let state = Arc::new(Mutex::new(MapState::default()));
+    let callback_state = state.clone();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let mut state = callback_state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    device.poll(wgpu::Maintain::Wait);
+    MapFuture { state }.await
+}
+
+/// Bytes per row of a texture copy into a buffer, padded to `COPY_BYTES_PER_ROW_ALIGNMENT`.
+pub(crate) fn padded_bytes_per_row(unpadded_bytes_per_row: u32) -> u32 {
+    unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_padded_to_256_bytes() {
+        assert_eq!(padded_bytes_per_row(4), 256);
+        assert_eq!(padded_bytes_per_row(255), 256);
+        assert_eq!(padded_bytes_per_row(257), 512);
+        // 67 RGBA pixels.
+        assert_eq!(padded_bytes_per_row(268), 512);
+    }
+
+    #[test]
+    fn exact_multiples_are_not_padded() {
+        assert_eq!(padded_bytes_per_row(256), 256);
+        // 1920 RGBA pixels.
+        assert_eq!(padded_bytes_per_row(7680), 7680);
+    }
+
+    #[test]
+    fn zero_width_needs_no_bytes() {
+        assert_eq!(padded_bytes_per_row(0), 0);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{fxaa::FxaaTarget, RenderGraph, State};
+
+/// What the render function set with `State::set_render_fn` records a frame with.
+pub struct RenderContext<'a> {
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// The texture `view` is a view of.
+    pub texture: &'a wgpu::Texture,
+    /// The surface texture of this frame, or the headless texture in headless mode.
+    pub view: &'a wgpu::TextureView,
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    state: &'a State,
+    msaa_view: Option<&'a wgpu::TextureView>,
+    queries: bool,
+}
+
+/// The textures `State::run_render_fn` records a frame into.
+pub(crate) struct FrameTarget<'a> {
+    pub texture: &'a wgpu::Texture,
+    pub view: &'a wgpu::TextureView,
+    /// The multisampled texture the scene is drawn into with MSAA, resolved into `view`.
+    pub msaa_view: Option<&'a wgpu::TextureView>,
+    /// The offscreen texture the scene is drawn into with FXAA, smoothed into `view`.
+    pub fxaa: Option<&'a FxaaTarget>,
+    /// Whether the passes write the GPU timestamps and occlusion queries, which only
+    /// `State::submit_frame` resolves.
+    pub queries: bool,
+}
+
+impl<'a> RenderContext<'a> {
+    /// Records the built-in passes into `view`: the color targets, then clearing to the clear
+    /// color and drawing the mesh and the triangle. This is all the default render function
+    /// does, later passes can draw on top of it with `LoadOp::Load`.
+    pub fn draw_scene(&mut self) {
+        self.state
+            .encode_render_pass(self.encoder, self.view, self.msaa_view, self.queries);
+    }
+
+    pub fn state(&self) -> &'a State {
+        self.state
+    }
+
+    /// The depth texture of the built-in passes, for own passes drawing on top of the scene.
+    pub fn depth_view(&self) -> &'a wgpu::TextureView {
+        &self.state.depth_view
+    }
+
+    /// The bind group of this frame's `FrameUniforms`, set it at group 1 to match
+    /// `FrameUniforms::WGSL`.
+    pub fn frame_uniforms_bind_group(&self) -> &'a wgpu::BindGroup {
+        &self.state.frame_uniforms.bind_group
+    }
+
+    /// A view of the frame's texture in `format`, which has to be the texture's format or one
+    /// of `State::view_formats`. `None` otherwise.
+    pub fn view_with_format(&self, format: wgpu::TextureFormat) -> Option<wgpu::TextureView> {
+        if format != self.texture.format() && !self.state.view_formats().contains(&format) {
+            return None;
+        }
+        Some(self.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(format),
+            ..Default::default()
+        }))
+    }
+
+    /// The occlusion queries of `State::set_occlusion_query_count`, for
+    /// `RenderPassDescriptor::occlusion_query_set`. `None` in `State::capture_frame`, which
+    /// doesn't resolve them.
+    pub fn occlusion_query_set(&self) -> Option<&'a wgpu::QuerySet> {
+        self.state.occlusion_query_set().filter(|_| self.queries)
+    }
+}
+
+impl State {
+    /// Replaces how frames are recorded, `render`, `render_to_texture` and `capture_frame` set
+    /// up the encoder and target and submit the frame afterwards. The default just calls
+    /// `RenderContext::draw_scene`.
+    pub fn set_render_fn(&mut self, render_fn: impl FnMut(&mut RenderContext) + 'static) {
+        self.render_fn = Box::new(render_fn);
+        self.request_redraw();
+    }
+
+    /// The target of a frame rendered into `view`, with the state's own MSAA and FXAA
+    /// textures.
+    pub(crate) fn frame_target<'a>(
+        &'a self,
+        texture: &'a wgpu::Texture,
+        view: &'a wgpu::TextureView,
+    ) -> FrameTarget<'a> {
+        FrameTarget {
+            texture,
+            view,
+            msaa_view: self.msaa_view.as_ref(),
+            fxaa: self.fxaa.as_ref().map(|fxaa| &fxaa.target),
+            queries: true,
+        }
+    }
+
+    /// Records a frame into `target` with the render function and the passes of the graph.
+    /// With FXAA they render into its offscreen texture, which is then smoothed into the
+    /// target's view.
+    pub(crate) fn run_render_fn(
+        &self,
+        render_fn: &mut dyn FnMut(&mut RenderContext),
+        render_graph: &mut RenderGraph,
+        encoder: &mut wgpu::CommandEncoder,
+        target: FrameTarget,
+    ) {
+        let (scene_texture, scene_view) = match target.fxaa {
+            Some(fxaa_target) => (&fxaa_target.texture, &fxaa_target.view),
+            None => (target.texture, target.view),
+        };
+        let mut context = RenderContext {
+            encoder,
+            texture: scene_texture,
+            view: scene_view,
+            device: &self.gpu.device,
+            queue: &self.gpu.queue,
+            state: self,
+            msaa_view: target.msaa_view,
+            queries: target.queries,
+        };
+        render_graph.run(&mut context, render_fn);
+        if let (Some(fxaa), Some(fxaa_target)) = (&self.fxaa, target.fxaa) {
+            fxaa.encode(encoder, fxaa_target, target.view);
+        }
+    }
+}
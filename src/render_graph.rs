@@ -0,0 +1,209 @@
+use crate::{RenderContext, RenderGraphError, State};
+
+type PassFn = Box<dyn FnMut(&mut RenderContext)>;
+
+struct GraphPass {
+    name: String,
+    /// Whether the pass depends on `RenderGraph::SCENE`, directly or through another pass.
+    after_scene: bool,
+    run: PassFn,
+}
+
+/// Named passes recorded around the render function every frame, see `State::add_pass`.
+/// Passes run after the passes they depend on. Passes that don't depend on
+/// `RenderGraph::SCENE` run before the render function, e.g. shadow passes, the others
+/// after it, e.g. post-processing or UI.
+#[derive(Default)]
+pub struct RenderGraph {
+    /// In execution order, the passes before the scene first.
+    passes: Vec<GraphPass>,
+}
+
+impl RenderGraph {
+    /// The name of the render function set with `State::set_render_fn`, which draws the scene
+    /// by default.
+    pub const SCENE: &'static str = "scene";
+
+    /// Adds a pass running after `deps`, which have to be added already or be
+    /// `RenderGraph::SCENE`. That way there can't be cycles.
+    pub fn add_pass(
+        &mut self,
+        name: impl Into<String>,
+        deps: &[&str],
+        pass: impl FnMut(&mut RenderContext) + 'static,
+    ) -> Result<(), RenderGraphError> {
+        let name = name.into();
+        if name == Self::SCENE || self.position(&name).is_some() {
+            return Err(RenderGraphError::DuplicatePass(name));
+        }
+        let mut after_scene = false;
+        for &dep in deps {
+            after_scene |= match self.position(dep) {
+                Some(index) => self.passes[index].after_scene,
+                None if dep == Self::SCENE => true,
+                None => return Err(RenderGraphError::UnknownDependency(dep.to_string())),
+            };
+        }
+        let pass = GraphPass {
+            name,
+            after_scene,
+            run: Box::new(pass),
+        };
+        // The passes before the scene only depend on each other, so adding a pass to the end
+        // of its group puts it after all its dependencies.
+        let index = if after_scene {
+            self.passes.len()
+        } else {
+            self.scene_index()
+        };
+        self.passes.insert(index, pass);
+        Ok(())
+    }
+
+    /// Removes every pass.
+    pub fn clear(&mut self) {
+        self.passes.clear();
+    }
+
+    /// The names of the passes in execution order, including `RenderGraph::SCENE`.
+    pub fn execution_order(&self) -> Vec<&str> {
+        let mut order: Vec<&str> = self.passes.iter().map(|pass| pass.name.as_str()).collect();
+        order.insert(self.scene_index(), Self::SCENE);
+        order
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.passes.iter().position(|pass| pass.name == name)
+    }
+
+    /// Where the render function runs between the passes.
+    fn scene_index(&self) -> usize {
+        self.passes
+            .iter()
+            .position(|pass| pass.after_scene)
+            .unwrap_or(self.passes.len())
+    }
+
+    /// Records every pass and `scene` in execution order.
+    pub(crate) fn run(
+        &mut self,
+        context: &mut RenderContext,
+        scene: &mut dyn FnMut(&mut RenderContext),
+    ) {
+        let scene_index = self.scene_index();
+        let (before, after) = self.passes.split_at_mut(scene_index);
+        for pass in before {
+            (pass.run)(context);
+        }
+        scene(context);
+        for pass in after {
+            (pass.run)(context);
+        }
+    }
+}
+
+impl State {
+    /// Adds a pass to the render graph, see `RenderGraph::add_pass`.
+    pub fn add_pass(
+        &mut self,
+        name: impl Into<String>,
+        deps: &[&str],
+        pass: impl FnMut(&mut RenderContext) + 'static,
+    ) -> Result<(), RenderGraphError> {
+        self.render_graph.add_pass(name, deps, pass)?;
+        self.request_redraw();
+        Ok(())
+    }
+
+    pub fn render_graph(&self) -> &RenderGraph {
+        &self.render_graph
+    }
+
+    pub fn render_graph_mut(&mut self) -> &mut RenderGraph {
+        &mut self.render_graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(passes: &[(&str, &[&str])]) -> RenderGraph {
+        let mut graph = RenderGraph::default();
+        for &(name, deps) in passes {
+            graph.add_pass(name, deps, |_| {}).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn empty_graph_only_runs_the_scene() {
+        assert_eq!(
+            RenderGraph::default().execution_order(),
+            [RenderGraph::SCENE]
+        );
+    }
+
+    #[test]
+    fn passes_without_the_scene_as_dependency_run_before_it() {
+        let graph = graph(&[("shadow", &[]), ("ui", &[RenderGraph::SCENE])]);
+        assert_eq!(graph.execution_order(), ["shadow", "scene", "ui"]);
+    }
+
+    #[test]
+    fn passes_run_after_their_dependencies() {
+        let graph = graph(&[
+            ("post", &[RenderGraph::SCENE]),
+            ("shadow", &[]),
+            ("ui", &["post"]),
+            ("shadow_blur", &["shadow"]),
+        ]);
+        assert_eq!(
+            graph.execution_order(),
+            ["shadow", "shadow_blur", "scene", "post", "ui"]
+        );
+    }
+
+    #[test]
+    fn depending_on_the_scene_through_another_pass_runs_after_it() {
+        let graph = graph(&[
+            ("post", &[RenderGraph::SCENE]),
+            ("ui", &["post"]),
+            ("depth", &[]),
+        ]);
+        assert_eq!(graph.execution_order(), ["depth", "scene", "post", "ui"]);
+    }
+
+    #[test]
+    fn unknown_dependencies_are_rejected() {
+        let mut graph = graph(&[("shadow", &[])]);
+        assert_eq!(
+            graph.add_pass("post", &["shadow", "bloom"], |_| {}),
+            Err(RenderGraphError::UnknownDependency("bloom".to_string()))
+        );
+        assert_eq!(graph.execution_order(), ["shadow", "scene"]);
+    }
+
+    #[test]
+    fn duplicate_passes_are_rejected() {
+        let mut graph = graph(&[("shadow", &[])]);
+        assert_eq!(
+            graph.add_pass("shadow", &[], |_| {}),
+            Err(RenderGraphError::DuplicatePass("shadow".to_string()))
+        );
+        assert_eq!(
+            graph.add_pass(RenderGraph::SCENE, &[], |_| {}),
+            Err(RenderGraphError::DuplicatePass(
+                RenderGraph::SCENE.to_string()
+            ))
+        );
+        assert_eq!(graph.execution_order(), ["shadow", "scene"]);
+    }
+
+    #[test]
+    fn clear_removes_every_pass() {
+        let mut graph = graph(&[("shadow", &[]), ("ui", &[RenderGraph::SCENE])]);
+        graph.clear();
+        assert_eq!(graph.execution_order(), [RenderGraph::SCENE]);
+    }
+}
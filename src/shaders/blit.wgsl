@@ -0,0 +1,27 @@
+// Copies a texture into a render target of a different size with linear filtering,
+// used to generate mipmaps by drawing one fullscreen triangle per level.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // A triangle covering the whole target, (0, 0), (2, 0) and (0, 2) in texture coordinates.
+    let tex_coords = vec2<f32>(f32((in_vertex_index << 1u) & 2u), f32(in_vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(tex_coords.x * 2.0 - 1.0, 1.0 - tex_coords.y * 2.0, 0.0, 1.0);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, in.tex_coords);
+}
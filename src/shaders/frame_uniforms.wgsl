@@ -0,0 +1,14 @@
+// The per-frame uniforms of `FrameUniforms`, paste this into a shader to use them.
+
+struct FrameUniforms {
+    // Seconds since the state was created, wraps around to 0 every hour.
+    time: f32,
+    // Frames rendered so far.
+    frame: u32,
+    // The size of the frame in pixels.
+    resolution: vec2<f32>,
+    // The cursor position in 0..1 from the top left, -1 while it's outside the window.
+    mouse: vec2<f32>,
+};
+@group(1) @binding(0)
+var<uniform> frame: FrameUniforms;
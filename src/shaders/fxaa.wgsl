@@ -0,0 +1,66 @@
+// Smooths the edges of the scene rendered into an offscreen texture while copying it into
+// the frame, a simplified FXAA: the edge direction is estimated from the luma of the
+// diagonal neighbors and the pixel is blurred along it.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    // A triangle covering the whole target, (0, 0), (2, 0) and (0, 2) in texture coordinates.
+    let tex_coords = vec2<f32>(f32((in_vertex_index << 1u) & 2u), f32(in_vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(tex_coords.x * 2.0 - 1.0, 1.0 - tex_coords.y * 2.0, 0.0, 1.0);
+    out.tex_coords = tex_coords;
+    return out;
+}
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+@group(0) @binding(1)
+var scene_sampler: sampler;
+
+// How far along the edge is blurred at most, in pixels.
+const SPAN_MAX: f32 = 8.0;
+const REDUCE_MUL: f32 = 0.125;
+const REDUCE_MIN: f32 = 0.0078125;
+
+// The samples are linear even for sRGB textures, the square root brings the luma close to
+// how bright it looks, which is what the edge detection needs.
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn sample(coords: vec2<f32>) -> vec3<f32> {
+    return textureSample(scene, scene_sampler, coords).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene));
+    let center = textureSample(scene, scene_sampler, in.tex_coords);
+    let luma_nw = luma(sample(in.tex_coords + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample(in.tex_coords + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(sample(in.tex_coords + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(sample(in.tex_coords + vec2<f32>(1.0, 1.0) * texel));
+    let luma_m = luma(center.rgb);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Perpendicular to the luma gradient, so along the edge.
+    var dir = vec2<f32>((luma_nw + luma_ne) - (luma_sw + luma_se), (luma_ne + luma_se) - (luma_nw + luma_sw));
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let near = 0.5 * (sample(in.tex_coords + dir * (1.0 / 3.0 - 0.5))
+        + sample(in.tex_coords + dir * (2.0 / 3.0 - 0.5)));
+    let far = near * 0.5 + 0.25 * (sample(in.tex_coords - dir * 0.5)
+        + sample(in.tex_coords + dir * 0.5));
+    // The wider blur crossed another edge if its luma left the neighborhood's range.
+    let luma_far = luma(far);
+    let outside = luma_far < luma_min || luma_far > luma_max;
+    return vec4<f32>(select(far, near, outside), center.a);
+}
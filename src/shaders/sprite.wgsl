@@ -0,0 +1,37 @@
+// Draws the sprites of `State::draw_sprite`, one instance per sprite. The four vertices of
+// each instance form a quad as a triangle strip.
+
+struct SpriteInput {
+    // Left, top, right and bottom edge in clip space.
+    @location(0) dest: vec4<f32>,
+    // Left, top, right and bottom edge in texture coordinates.
+    @location(1) uv: vec4<f32>,
+    @location(2) tint: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var t: texture_2d<f32>;
+@group(0) @binding(1)
+var s: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, sprite: SpriteInput) -> VertexOutput {
+    // 0 is the top left, 1 the bottom left, 2 the top right and 3 the bottom right corner.
+    let corner = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(mix(sprite.dest.xy, sprite.dest.zw, corner), 0.0, 1.0);
+    out.uv = mix(sprite.uv.xy, sprite.uv.zw, corner);
+    out.tint = sprite.tint;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t, s, in.uv) * in.tint;
+}
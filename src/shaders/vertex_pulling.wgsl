@@ -0,0 +1,33 @@
+// Draws the vertices of `State::set_vertex_storage`, read from a storage buffer by
+// `vertex_index` instead of a vertex buffer.
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// Tightly packed `Vertex`es, six floats each. An array of structs with `vec3`s would be
+// padded to 16 bytes per `vec3` in a storage buffer.
+@group(1) @binding(0)
+var<storage, read> vertices: array<f32>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let i = vertex_index * 6u;
+    let position = vec3<f32>(vertices[i], vertices[i + 1u], vertices[i + 2u]);
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
+    out.color = vec3<f32>(vertices[i + 3u], vertices[i + 4u], vertices[i + 5u]);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,165 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::State;
+
+/// A depth texture to render shadow casters into with `State::begin_shadow_pass`, ready to
+/// be sampled in a shader as `@binding(0) var t: texture_depth_2d` and
+/// `@binding(1) var s: sampler_comparison`, e.g. with `textureSampleCompare`.
+#[derive(Debug)]
+pub struct ShadowMap {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    /// Compares with `LessEqual`, so a fragment is lit if it's not further from the light than
+    /// the closest caster.
+    pub sampler: wgpu::Sampler,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ShadowMap {
+    /// The depth format of every shadow map, pipelines drawing into it have to use it.
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+}
+
+/// A render pass with only the depth attachment of a shadow map, use it like a
+/// `wgpu::RenderPass`. The pass is submitted when this is dropped. Occlusion queries from
+/// `State::set_occlusion_query_count` can be used in it.
+pub struct ShadowPass<'a> {
+    // An `Option` so it can be ended before the encoder is finished in `drop`.
+    pass: Option<wgpu::RenderPass<'static>>,
+    encoder: Option<wgpu::CommandEncoder>,
+    queue: &'a wgpu::Queue,
+}
+
+impl Deref for ShadowPass<'_> {
+    type Target = wgpu::RenderPass<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        self.pass.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ShadowPass<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.pass.as_mut().unwrap()
+    }
+}
+
+impl Drop for ShadowPass<'_> {
+    fn drop(&mut self) {
+        self.pass = None;
+        if let Some(encoder) = self.encoder.take() {
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+    }
+}
+
+impl State {
+    /// Creates a square shadow map, `size` is clamped to the device's maximum texture size.
+    pub fn create_shadow_map(&self, size: u32) -> ShadowMap {
+        let device = &self.gpu.device;
+        let size = size.clamp(1, device.limits().max_texture_dimension_2d);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ShadowMap::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Map Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            // Filtering a comparison sampler blends the results of neighboring texels, which
+            // softens the shadow edges.
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Map Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Map Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        ShadowMap {
+            texture,
+            view,
+            sampler,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    /// Starts a pass that clears `shadow_map` to the far plane and draws into its depth only.
+    /// Pipelines used in it need no fragment targets and `ShadowMap::FORMAT` as depth format.
+    pub fn begin_shadow_pass<'a>(&'a self, shadow_map: &ShadowMap) -> ShadowPass<'a> {
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Shadow Encoder"),
+            });
+        let pass = encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &shadow_map.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: self.occlusion_query_set(),
+                timestamp_writes: None,
+            })
+            // The encoder is kept next to the pass, which is always dropped first.
+            .forget_lifetime();
+        ShadowPass {
+            pass: Some(pass),
+            encoder: Some(encoder),
+            queue: &self.gpu.queue,
+        }
+    }
+}
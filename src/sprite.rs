@@ -0,0 +1,341 @@
+use std::ops::Range;
+
+use crate::{pipeline::TargetFormats, LoadedTexture, State};
+
+/// A rectangle in pixels, from the top left.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// The texture coordinates of `source`, in pixels of `texture`, the whole texture for
+/// `None`.
+pub(crate) fn source_uv(texture: &wgpu::Texture, source: Option<Rect>) -> [f32; 4] {
+    match source {
+        Some(source) => {
+            let width = texture.width() as f32;
+            let height = texture.height() as f32;
+            [
+                source.x / width,
+                source.y / height,
+                (source.x + source.width) / width,
+                (source.y + source.height) / height,
+            ]
+        }
+        None => [0.0, 0.0, 1.0, 1.0],
+    }
+}
+
+/// Matches the `SpriteInput` of `shaders/sprite.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SpriteInstance {
+    dest: [f32; 4],
+    uv: [f32; 4],
+    tint: [f32; 4],
+}
+
+// SAFETY: `SpriteInstance` is `repr(C)`, only contains `f32`s and has no padding.
+unsafe impl bytemuck::Zeroable for SpriteInstance {}
+unsafe impl bytemuck::Pod for SpriteInstance {}
+
+impl SpriteInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4];
+
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &Self::ATTRIBUTES,
+    };
+}
+
+/// The sprites of one texture.
+struct SpriteGroup {
+    /// Of the texture's bind group, the texture itself can't be kept.
+    id: wgpu::Id<wgpu::BindGroup>,
+    bind_group: wgpu::BindGroup,
+    /// In pixels, drawn since the last upload.
+    sprites: Vec<(Rect, [f32; 4], [f32; 4])>,
+    /// Where the uploaded sprites are in the instance buffer.
+    instances: Range<u32>,
+}
+
+/// Collects the sprites of a frame by texture and draws them with one instanced draw per
+/// texture.
+pub(crate) struct SpriteBatch {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// Only grows, like the mesh instance buffer.
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    /// In the order their textures were first drawn in the frame.
+    groups: Vec<SpriteGroup>,
+}
+
+impl SpriteBatch {
+    pub fn new(device: &wgpu::Device, targets: TargetFormats) -> Self {
+        // The same as the layout of every `LoadedTexture`.
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline = create_pipeline(device, &bind_group_layout, targets);
+        Self {
+            pipeline,
+            bind_group_layout,
+            buffer: Self::create_buffer(device, 1),
+            capacity: 1,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Recreates the pipeline after the targets changed, the sprites are kept.
+    pub fn recreate_pipeline(&mut self, device: &wgpu::Device, targets: TargetFormats) {
+        self.pipeline = create_pipeline(device, &self.bind_group_layout, targets);
+    }
+
+    pub fn push(
+        &mut self,
+        device: &wgpu::Device,
+        texture: &LoadedTexture,
+        sprite: (Rect, [f32; 4], [f32; 4]),
+    ) {
+        let id = texture.bind_group.global_id();
+        let index = match self.groups.iter().position(|group| group.id == id) {
+            Some(index) => index,
+            None => {
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Sprite Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                        },
+                    ],
+                });
+                self.groups.push(SpriteGroup {
+                    id,
+                    bind_group,
+                    sprites: Vec::new(),
+                    instances: 0..0,
+                });
+                self.groups.len() - 1
+            }
+        };
+        self.groups[index].sprites.push(sprite);
+    }
+
+    /// Uploads the sprites drawn since the last upload for the next `draw` and starts
+    /// collecting the next frame, through the frame's staging belt. `viewport` is the size in
+    /// pixels the sprites are placed in.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        viewport: (f32, f32),
+    ) {
+        // The bind groups of textures that weren't drawn are dropped, so they don't keep the
+        // textures alive.
+        self.groups.retain(|group| !group.sprites.is_empty());
+        let (width, height) = viewport;
+        let mut instances = Vec::new();
+        for group in &mut self.groups {
+            let start = instances.len() as u32;
+            instances.extend(
+                group
+                    .sprites
+                    .drain(..)
+                    .map(|(dest, uv, tint)| SpriteInstance {
+                        dest: [
+                            dest.x / width * 2.0 - 1.0,
+                            1.0 - dest.y / height * 2.0,
+                            (dest.x + dest.width) / width * 2.0 - 1.0,
+                            1.0 - (dest.y + dest.height) / height * 2.0,
+                        ],
+                        uv,
+                        tint,
+                    }),
+            );
+            group.instances = start..instances.len() as u32;
+        }
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.capacity);
+        }
+        let data: &[u8] = bytemuck::cast_slice(&instances);
+        if let Some(size) = wgpu::BufferSize::new(data.len() as u64) {
+            staging_belt
+                .write_buffer(encoder, &self.buffer, 0, size, device)
+                .copy_from_slice(data);
+        }
+    }
+
+    /// Draws the uploaded sprites, one draw per texture.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.groups.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.buffer.slice(..));
+        for group in &self.groups {
+            render_pass.set_bind_group(0, &group.bind_group, &[]);
+            render_pass.draw(0..4, group.instances.clone());
+        }
+    }
+
+    /// The number of draws of the uploaded sprites.
+    pub fn draw_calls(&self) -> u32 {
+        self.groups.len() as u32
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Instance Buffer"),
+            size: (capacity * std::mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+/// Creates the pipeline drawing the sprites with alpha blending on top of the scene, they
+/// leave the depth buffer alone.
+fn create_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/sprite.wgsl"));
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Sprite Pipeline Layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Sprite Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[SpriteInstance::LAYOUT],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: targets.color,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: targets.depth,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: targets.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+impl State {
+    /// Draws the whole `texture` into `dest` in the next frame, multiplied by `tint` (linear
+    /// RGBA, `[1.0; 4]` keeps the texture's colors). `dest` is in pixels of the scene, the
+    /// letterbox viewport with a target aspect ratio. Sprites are drawn on top of the scene
+    /// with alpha blending, but grouped by texture: all sprites of a texture are drawn in
+    /// one draw call, in the order the textures were first drawn in the frame, so overlapping
+    /// sprites of different textures may end up in another order than they were drawn in.
+    /// Call it every frame the sprite should be visible, e.g. before `render`. In
+    /// `RenderMode::OnDemand` a redraw has to be requested.
+    pub fn draw_sprite(&mut self, texture: &LoadedTexture, dest: Rect, tint: [f32; 4]) {
+        self.draw_sprite_region(texture, None, dest, tint);
+    }
+
+    /// Like `draw_sprite`, but only draws `source`, in pixels of the texture, e.g. one frame
+    /// of a texture atlas. `None` draws the whole texture.
+    pub fn draw_sprite_region(
+        &mut self,
+        texture: &LoadedTexture,
+        source: Option<Rect>,
+        dest: Rect,
+        tint: [f32; 4],
+    ) {
+        let uv = source_uv(&texture.texture, source);
+        let targets = self.target_formats();
+        self.sprite_batch
+            .get_or_insert_with(|| SpriteBatch::new(&self.gpu.device, targets))
+            .push(&self.gpu.device, texture, (dest, uv, tint));
+    }
+
+    /// The draw calls the sprites of the last frame took, one per texture.
+    pub fn sprite_draw_calls(&self) -> u32 {
+        self.sprite_batch
+            .as_ref()
+            .map_or(0, SpriteBatch::draw_calls)
+    }
+
+    /// Uploads the sprites drawn since the last frame.
+    pub(crate) fn upload_sprites(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let viewport = match self.letterbox_viewport() {
+            Some((_, _, width, height)) => (width as f32, height as f32),
+            None => (
+                self.surface_config.width.max(1) as f32,
+                self.surface_config.height.max(1) as f32,
+            ),
+        };
+        if let Some(sprite_batch) = &mut self.sprite_batch {
+            sprite_batch.upload(&self.gpu.device, &mut self.staging_belt, encoder, viewport);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use ab_glyph::{Font, FontVec, GlyphId, InvalidFont, PxScale, ScaleFont};
+
+use crate::{
+    logging::log_warn,
+    sprite::{self, Rect, SpriteBatch},
+    LoadedTexture, State, TextureOptions,
+};
+
+/// The width and height of the glyph atlas in pixels, enough for a few thousand glyphs of
+/// a typical UI size.
+const ATLAS_SIZE: u32 = 1024;
+/// The empty pixels right of and below every glyph in the atlas, so the linear sampler
+/// doesn't pick up the neighbors.
+const GLYPH_PADDING: u32 = 1;
+
+/// Packs rectangles into rows from the top left of a square atlas, a row is as high as its
+/// tallest rectangle.
+#[derive(Debug)]
+struct ShelfPacker {
+    size: u32,
+    /// The top left corner of the next rectangle in the current row.
+    x: u32,
+    y: u32,
+    row_height: u32,
+}
+
+impl ShelfPacker {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            x: 0,
+            y: 0,
+            row_height: 0,
+        }
+    }
+
+    /// The top left corner of a `width` by `height` rectangle, `None` if the atlas is full.
+    fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.size {
+            return None;
+        }
+        if self.x + width > self.size {
+            self.x = 0;
+            self.y += self.row_height;
+            self.row_height = 0;
+        }
+        if self.y + height > self.size {
+            return None;
+        }
+        let position = (self.x, self.y);
+        self.x += width;
+        self.row_height = self.row_height.max(height);
+        Some(position)
+    }
+}
+
+/// A rasterized glyph in the atlas.
+#[derive(Debug, Clone, Copy)]
+struct CachedGlyph {
+    /// In pixels of the atlas.
+    source: Rect,
+    /// From the glyph's origin on the baseline to the top left corner of `source`.
+    offset: (f32, f32),
+}
+
+/// The font of `State::draw_text` and the atlas its glyphs are rasterized into when they
+/// are first drawn.
+pub(crate) struct TextRenderer {
+    font: FontVec,
+    scale: PxScale,
+    atlas: LoadedTexture,
+    packer: ShelfPacker,
+    /// `None` for glyphs without an outline, like spaces, and those that didn't fit.
+    glyphs: HashMap<GlyphId, Option<CachedGlyph>>,
+}
+
+impl TextRenderer {
+    fn new(state: &State, font: FontVec, scale: PxScale) -> Self {
+        let atlas = state
+            .load_texture(
+                &vec![0; (ATLAS_SIZE * ATLAS_SIZE * 4) as usize],
+                ATLAS_SIZE,
+                ATLAS_SIZE,
+                TextureOptions::default(),
+            )
+            .expect("the atlas has a valid size");
+        Self {
+            font,
+            scale,
+            atlas,
+            packer: ShelfPacker::new(ATLAS_SIZE),
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// The same font with a new atlas on the device of `state`, e.g. after the old one was
+    /// lost.
+    pub fn recreate(self, state: &State) -> Self {
+        Self::new(state, self.font, self.scale)
+    }
+
+    /// Rasterizes the glyph into the atlas the first time it's drawn.
+    fn glyph(&mut self, queue: &wgpu::Queue, id: GlyphId) -> Option<CachedGlyph> {
+        if let Some(glyph) = self.glyphs.get(&id) {
+            return *glyph;
+        }
+        let glyph = self.rasterize(queue, id);
+        self.glyphs.insert(id, glyph);
+        glyph
+    }
+
+    fn rasterize(&mut self, queue: &wgpu::Queue, id: GlyphId) -> Option<CachedGlyph> {
+        let outline = self.font.outline_glyph(id.with_scale(self.scale))?;
+        // Whole pixels, relative to the origin.
+        let bounds = outline.px_bounds();
+        let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let Some((x, y)) = self
+            .packer
+            .pack(width + GLYPH_PADDING, height + GLYPH_PADDING)
+        else {
+            log_warn!("The glyph atlas is full, {id:?} isn't drawn");
+            return None;
+        };
+        // White with the coverage as alpha, so the tint sets the color.
+        let mut rgba = [255, 255, 255, 0].repeat((width * height) as usize);
+        outline.draw(|px, py, coverage| {
+            rgba[((py * width + px) * 4 + 3) as usize] = (coverage.min(1.0) * 255.0).round() as u8;
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.atlas.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        Some(CachedGlyph {
+            source: Rect::new(x as f32, y as f32, width as f32, height as f32),
+            offset: (bounds.min.x, bounds.min.y),
+        })
+    }
+}
+
+/// The glyphs of `text` and their origins on the baseline, relative to the top left corner
+/// of the first line. `\n` starts a new line, other control characters are skipped.
+fn layout(font: &impl Font, scale: PxScale, text: &str) -> Vec<(GlyphId, f32, f32)> {
+    let font = font.as_scaled(scale);
+    let line_height = font.height() + font.line_gap();
+    let (mut caret, mut baseline) = (0.0, font.ascent());
+    let mut previous = None;
+    let mut glyphs = Vec::new();
+    for c in text.chars() {
+        if c == '\n' {
+            caret = 0.0;
+            baseline += line_height;
+            previous = None;
+            continue;
+        }
+        if c.is_control() {
+            continue;
+        }
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, id);
+        }
+        glyphs.push((id, caret, baseline));
+        caret += font.h_advance(id);
+        previous = Some(id);
+    }
+    glyphs
+}
+
+impl State {
+    /// Sets the font `draw_text` uses, TrueType or OpenType data, e.g. from `include_bytes!`.
+    /// `px_size` is the height of the glyphs in pixels. The crate ships no font.
+    pub fn set_font(&mut self, font_data: Vec<u8>, px_size: f32) -> Result<(), InvalidFont> {
+        let font = FontVec::try_from_vec(font_data)?;
+        self.text = Some(TextRenderer::new(self, font, PxScale::from(px_size)));
+        Ok(())
+    }
+
+    /// Draws `text` with its top left corner at `x`, `y` in the next frame, in `color`
+    /// (linear RGBA). The glyphs are sprites, see `draw_sprite`, so they are in pixels of the
+    /// scene, drawn on top of it and have to be drawn every frame. `\n` starts a new line.
+    /// Does nothing without a font, see `set_font`.
+    pub fn draw_text(&mut self, text: &str, x: f32, y: f32, color: [f32; 4]) {
+        let targets = self.target_formats();
+        let Some(renderer) = &mut self.text else {
+            return;
+        };
+        let sprite_batch = self
+            .sprite_batch
+            .get_or_insert_with(|| SpriteBatch::new(&self.gpu.device, targets));
+        for (id, caret, baseline) in layout(&renderer.font, renderer.scale, text) {
+            let Some(glyph) = renderer.glyph(&self.gpu.queue, id) else {
+                continue;
+            };
+            // Rounded to whole pixels, so the glyph's texels aren't blurred by the sampler.
+            let dest = Rect::new(
+                (x + caret + glyph.offset.0).round(),
+                (y + baseline + glyph.offset.1).round(),
+                glyph.source.width,
+                glyph.source.height,
+            );
+            let uv = sprite::source_uv(&renderer.atlas.texture, Some(glyph.source));
+            sprite_batch.push(&self.gpu.device, &renderer.atlas, (dest, uv, color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangles_fill_rows() {
+        let mut packer = ShelfPacker::new(10);
+        assert_eq!(packer.pack(4, 3), Some((0, 0)));
+        assert_eq!(packer.pack(4, 5), Some((4, 0)));
+        // Doesn't fit into the row anymore, the next one starts below the tallest.
+        assert_eq!(packer.pack(3, 2), Some((0, 5)));
+        assert_eq!(packer.pack(7, 2), Some((3, 5)));
+    }
+
+    #[test]
+    fn full_atlases_reject_rectangles() {
+        let mut packer = ShelfPacker::new(10);
+        assert_eq!(packer.pack(11, 1), None);
+        assert_eq!(packer.pack(10, 11), None);
+        assert_eq!(packer.pack(10, 8), Some((0, 0)));
+        assert_eq!(packer.pack(5, 3), None);
+        // Smaller ones may still fit.
+        assert_eq!(packer.pack(5, 2), Some((0, 8)));
+    }
+
+    /// A font of the system for the layout tests, which are skipped without it.
+    fn system_font() -> Option<FontVec> {
+        let data = std::fs::read("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf").ok()?;
+        FontVec::try_from_vec(data).ok()
+    }
+
+    #[test]
+    fn glyphs_advance_along_the_baseline() {
+        let Some(font) = system_font() else {
+            return;
+        };
+        let scale = PxScale::from(20.0);
+        let glyphs = layout(&font, scale, "ab");
+        let scaled = font.as_scaled(scale);
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(glyphs[0], (font.glyph_id('a'), 0.0, scaled.ascent()));
+        let advance = scaled.h_advance(font.glyph_id('a'))
+            + scaled.kern(font.glyph_id('a'), font.glyph_id('b'));
+        assert_eq!(glyphs[1], (font.glyph_id('b'), advance, scaled.ascent()));
+    }
+
+    #[test]
+    fn newlines_start_a_new_line() {
+        let Some(font) = system_font() else {
+            return;
+        };
+        let scale = PxScale::from(20.0);
+        let glyphs = layout(&font, scale, "a\n\tb");
+        let scaled = font.as_scaled(scale);
+        let line_height = scaled.height() + scaled.line_gap();
+        assert_eq!(glyphs.len(), 2);
+        assert_eq!(
+            glyphs[1],
+            (font.glyph_id('b'), 0.0, scaled.ascent() + line_height)
+        );
+    }
+}
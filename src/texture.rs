@@ -0,0 +1,160 @@
+use crate::{mipmap, State, TextureError};
+
+/// A sampled texture uploaded with `State::load_texture`, ready to be bound in a shader as
+/// `@binding(0) var t: texture_2d<f32>` and `@binding(1) var s: sampler`.
+#[derive(Debug)]
+pub struct LoadedTexture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+/// How `State::load_texture` creates the texture and its sampler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureOptions {
+    /// Creates the full mip chain, which avoids shimmering when the texture is drawn smaller
+    /// than it is.
+    pub generate_mipmaps: bool,
+    /// The maximum anisotropy of the sampler, 1 to 16. Higher values keep textures viewed at
+    /// grazing angles sharper. Ignored if the device doesn't support anisotropic filtering.
+    pub anisotropy_clamp: u16,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            generate_mipmaps: false,
+            anisotropy_clamp: 1,
+        }
+    }
+}
+
+impl State {
+    /// Uploads 8 bit sRGB RGBA pixels, row by row from the top left, into a texture with a
+    /// linear sampler. Any size works, it doesn't need to be a power of two.
+    /// Decode PNG or JPEG files with your own image crate first, see the crate docs.
+    pub fn load_texture(
+        &self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        options: TextureOptions,
+    ) -> Result<LoadedTexture, TextureError> {
+        if width == 0 || height == 0 {
+            return Err(TextureError::ZeroSize);
+        }
+        let expected = width as usize * height as usize * 4;
+        if rgba.len() != expected {
+            return Err(TextureError::DataSize {
+                expected,
+                actual: rgba.len(),
+            });
+        }
+        let device = &self.gpu.device;
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let (mip_level_count, usage) = if options.generate_mipmaps {
+            // The mip levels are rendered from the previous level.
+            (
+                mipmap::mip_level_count(width, height),
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+        } else {
+            (1, wgpu::TextureUsages::empty())
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Loaded Texture"),
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | usage,
+            view_formats: &[],
+        });
+        // Unlike buffer copies, `write_texture` doesn't need the rows padded to 256 bytes.
+        self.gpu.queue.write_texture(
+            texture.as_image_copy(),
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+        mipmap::generate_mipmaps(device, &self.gpu.queue, &texture);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let anisotropic_filtering = self
+            .gpu
+            .adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING);
+        // wgpu rejects values outside of 1..=16.
+        let anisotropy_clamp = if anisotropic_filtering {
+            options.anisotropy_clamp.clamp(1, 16)
+        } else {
+            1
+        };
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Loaded Texture Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            // Anisotropic filtering needs all filters to be linear.
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy_clamp,
+            ..Default::default()
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Loaded Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Loaded Texture Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        Ok(LoadedTexture {
+            texture,
+            view,
+            sampler,
+            bind_group_layout,
+            bind_group,
+        })
+    }
+}
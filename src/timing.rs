@@ -0,0 +1,255 @@
+use std::{collections::VecDeque, time::Duration};
+
+// std's Instant panics in the browser.
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// Measures frame times over a rolling window of the last `window_size` frames.
+#[derive(Debug, Clone)]
+pub struct FrameTimer {
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    window_size: usize,
+    frame_count: u64,
+}
+
+impl FrameTimer {
+    pub fn new(window_size: usize) -> Self {
+        let window_size = window_size.max(1);
+        Self {
+            last_frame: None,
+            frame_times: VecDeque::with_capacity(window_size),
+            window_size,
+            frame_count: 0,
+        }
+    }
+
+    /// Call once per frame. The first tick only records the start time.
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        match self.last_frame.replace(now) {
+            Some(last_frame) => self.add_frame_time(now - last_frame),
+            None => self.frame_count += 1,
+        }
+    }
+
+    /// Counts a frame that took `frame_time` instead of measuring it, e.g. to replay recorded
+    /// frame times.
+    pub fn add_frame_time(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == self.window_size {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.frame_count += 1;
+    }
+
+    /// Forgets when the last frame was, so the time until the next tick isn't counted as a
+    /// frame time, e.g. after rendering was paused.
+    pub fn restart(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// The average frame time over the rolling window, `None` until two frames were ticked.
+    pub fn average_frame_time(&self) -> Option<Duration> {
+        if self.frame_times.is_empty() {
+            return None;
+        }
+        Some(self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32)
+    }
+
+    /// Frames per second over the rolling window, 0 until two frames were ticked.
+    pub fn fps(&self) -> f64 {
+        match self.average_frame_time() {
+            Some(frame_time) if !frame_time.is_zero() => 1.0 / frame_time.as_secs_f64(),
+            _ => 0.0,
+        }
+    }
+
+    /// The number of ticks since the timer was created.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    pub fn set_window_size(&mut self, window_size: usize) {
+        self.window_size = window_size.max(1);
+        while self.frame_times.len() > self.window_size {
+            self.frame_times.pop_front();
+        }
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
+
+/// Accumulates real time and splits it into fixed simulation steps, independent of the
+/// frame rate.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+    last_advance: Option<Instant>,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            max_steps: 5,
+            accumulator: Duration::ZERO,
+            last_advance: None,
+        }
+    }
+
+    /// Adds the time since the last call and returns how many steps to simulate.
+    /// The first call only records the start time.
+    pub fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        let elapsed = match self.last_advance.replace(now) {
+            Some(last_advance) => now - last_advance,
+            None => Duration::ZERO,
+        };
+        self.advance_by(elapsed)
+    }
+
+    /// Like `advance`, but adds `elapsed` instead of measuring the time since the last call.
+    pub fn advance_by(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        if self.step.is_zero() {
+            return 0;
+        }
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+            // After a long stall, drop the time that is left instead of trying to catch up,
+            // otherwise each frame would take longer than the last.
+            if steps == self.max_steps {
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+        }
+        steps
+    }
+
+    /// How far the time is between the last and the next step in `0.0..1.0`, use it to
+    /// interpolate between the previous and the current simulation state when rendering.
+    pub fn alpha(&self) -> f32 {
+        if self.step.is_zero() {
+            return 0.0;
+        }
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step;
+    }
+
+    /// The most steps a single `advance` returns.
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps.max(1);
+    }
+}
+
+impl Default for FixedTimestep {
+    /// 60 Hz.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn frame_timer_averages_over_the_window() {
+        let mut timer = FrameTimer::new(3);
+        assert_eq!(timer.average_frame_time(), None);
+        assert_eq!(timer.fps(), 0.0);
+        for frame_time in [10, 20, 30] {
+            timer.add_frame_time(frame_time * MS);
+        }
+        assert_eq!(timer.average_frame_time(), Some(20 * MS));
+        assert_eq!(timer.fps(), 50.0);
+        // The first frame time drops out of the window.
+        timer.add_frame_time(40 * MS);
+        assert_eq!(timer.average_frame_time(), Some(30 * MS));
+        assert_eq!(timer.frame_count(), 4);
+    }
+
+    #[test]
+    fn frame_timer_keeps_the_latest_frames_when_the_window_shrinks() {
+        let mut timer = FrameTimer::new(4);
+        for frame_time in [10, 20, 30, 40] {
+            timer.add_frame_time(frame_time * MS);
+        }
+        timer.set_window_size(2);
+        assert_eq!(timer.average_frame_time(), Some(35 * MS));
+        timer.set_window_size(0);
+        assert_eq!(timer.window_size(), 1);
+        assert_eq!(timer.average_frame_time(), Some(40 * MS));
+    }
+
+    #[test]
+    fn frame_timer_only_records_the_start_on_the_first_tick() {
+        let mut timer = FrameTimer::default();
+        timer.tick();
+        assert_eq!(timer.frame_count(), 1);
+        assert_eq!(timer.average_frame_time(), None);
+        timer.tick();
+        assert_eq!(timer.frame_count(), 2);
+        assert!(timer.average_frame_time().is_some());
+    }
+
+    #[test]
+    fn fixed_timestep_accumulates_partial_steps() {
+        let mut timestep = FixedTimestep::new(10 * MS);
+        assert_eq!(timestep.advance_by(4 * MS), 0);
+        assert!((timestep.alpha() - 0.4).abs() < 1e-6);
+        assert_eq!(timestep.advance_by(7 * MS), 1);
+        assert!((timestep.alpha() - 0.1).abs() < 1e-6);
+        assert_eq!(timestep.advance_by(29 * MS), 3);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn fixed_timestep_drops_time_beyond_the_max_steps() {
+        let mut timestep = FixedTimestep::new(10 * MS);
+        timestep.set_max_steps(3);
+        assert_eq!(timestep.advance_by(1005 * MS), 3);
+        // Only the partial step is kept.
+        assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance_by(5 * MS), 1);
+        timestep.set_max_steps(0);
+        assert_eq!(timestep.max_steps(), 1);
+    }
+
+    #[test]
+    fn fixed_timestep_with_a_zero_step_never_steps() {
+        let mut timestep = FixedTimestep::new(Duration::ZERO);
+        assert_eq!(timestep.advance_by(100 * MS), 0);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::pipeline::{self, TargetFormats};
+
+/// Creates the pipeline for the example triangle, it has no vertex buffers because
+/// the vertices are hard-coded in the shader.
+pub(crate) fn create_pipeline(
+    device: &wgpu::Device,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/triangle.wgsl"));
+    pipeline::create_render_pipeline(device, "Triangle Pipeline", &shader, &[], &[], targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use pollster::FutureExt;
+
+    use super::*;
+    use crate::DEPTH_FORMAT;
+
+    #[test]
+    fn pipeline_is_created_on_the_default_device() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .block_on()
+        else {
+            // No adapter, e.g. in CI without a software renderer.
+            return;
+        };
+        let (device, _queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .block_on()
+            .unwrap();
+        for sample_count in [1, 4] {
+            device.push_error_scope(wgpu::ErrorFilter::Validation);
+            create_pipeline(
+                &device,
+                TargetFormats {
+                    color: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    depth: DEPTH_FORMAT,
+                    sample_count,
+                    blend: wgpu::BlendState::REPLACE,
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+            );
+            let error = device.pop_error_scope().block_on();
+            assert!(error.is_none(), "{sample_count} samples: {error:?}");
+        }
+    }
+}
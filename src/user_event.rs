@@ -0,0 +1,15 @@
+use std::{any::Any, sync::Arc};
+
+/// Sent through the `EventLoopProxy` of the event loop created by `crate::event_loop`, e.g.
+/// from a thread that finished loading an asset.
+#[derive(Debug, Clone)]
+pub enum UserEvent {
+    /// Requests a redraw of all windows, e.g. in `RenderMode::OnDemand`.
+    Redraw,
+    /// A job of `State::spawn_load` finished, sent to wake up the event loop. The states
+    /// call the `on_done` callbacks of their finished jobs when they get it.
+    LoadFinished,
+    /// Any message of the application, collected by every state for
+    /// `State::take_custom_events`. Use `Arc::downcast` to get the message back.
+    Custom(Arc<dyn Any + Send + Sync>),
+}
@@ -0,0 +1,145 @@
+use wgpu::util::DeviceExt;
+
+use crate::{
+    pipeline::{self, TargetFormats},
+    State, Vertex, VertexStorageError,
+};
+
+/// The vertices of `State::set_vertex_storage` and the pipeline pulling them.
+pub(crate) struct VertexStorage {
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    vertex_count: u32,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl VertexStorage {
+    pub fn new(
+        device: &wgpu::Device,
+        data: &[u8],
+        camera_layout: &wgpu::BindGroupLayout,
+        targets: TargetFormats,
+    ) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Storage Buffer"),
+            contents: data,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Vertex Storage Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Vertex Storage Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        let pipeline = create_pipeline(device, camera_layout, &bind_group_layout, targets);
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+            vertex_count: (data.len() / std::mem::size_of::<Vertex>()) as u32,
+            pipeline,
+        }
+    }
+
+    /// Recreates the pipeline after the targets changed, the vertices are kept.
+    pub fn recreate_pipeline(
+        &mut self,
+        device: &wgpu::Device,
+        camera_layout: &wgpu::BindGroupLayout,
+        targets: TargetFormats,
+    ) {
+        self.pipeline = create_pipeline(device, camera_layout, &self.bind_group_layout, targets);
+    }
+
+    /// Draws the vertices as a triangle list, the camera has to be bound at group 0.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    camera_layout: &wgpu::BindGroupLayout,
+    storage_layout: &wgpu::BindGroupLayout,
+    targets: TargetFormats,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/vertex_pulling.wgsl"));
+    pipeline::create_render_pipeline(
+        device,
+        "Vertex Pulling Pipeline",
+        &shader,
+        &[camera_layout, storage_layout],
+        &[],
+        targets,
+    )
+}
+
+impl State {
+    /// Replaces the vertices drawn every frame from a storage buffer, empty data removes them.
+    /// `data` holds tightly packed `Vertex`es, e.g. from `bytemuck::cast_slice`, which the
+    /// shader reads by `vertex_index` as a triangle list. They are transformed by the camera
+    /// like the mesh, but not instanced. Fails without storage buffers in vertex shaders,
+    /// e.g. on WebGL2, or if the data doesn't fit into one storage buffer binding.
+    pub fn set_vertex_storage(&mut self, data: &[u8]) -> Result<(), VertexStorageError> {
+        if !data.len().is_multiple_of(std::mem::size_of::<Vertex>()) {
+            return Err(VertexStorageError::DataSize(data.len()));
+        }
+        if data.is_empty() {
+            self.vertex_storage = None;
+            self.request_redraw();
+            return Ok(());
+        }
+        let supported = self
+            .gpu
+            .adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::VERTEX_STORAGE);
+        let limits = self.gpu.limits();
+        if !supported || limits.max_storage_buffers_per_shader_stage == 0 {
+            return Err(VertexStorageError::Unsupported);
+        }
+        let max = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        if data.len() as u64 > max {
+            return Err(VertexStorageError::TooLarge {
+                size: data.len() as u64,
+                max,
+            });
+        }
+        self.vertex_storage = Some(VertexStorage::new(
+            &self.gpu.device,
+            data,
+            &self.camera.bind_group_layout,
+            self.target_formats(),
+        ));
+        self.request_redraw();
+        Ok(())
+    }
+
+    /// The storage buffer of `set_vertex_storage`, e.g. to bind it in an own compute pass.
+    pub fn vertex_storage_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.vertex_storage.as_ref().map(VertexStorage::buffer)
+    }
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use std::{cell::Cell, rc::Rc};
+
+use pollster::FutureExt;
+use winit_wgpu_min::{CaptureError, RenderContext, RenderGraph, StateConfig};
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 1.0,
+    g: 0.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+#[test]
+fn capture_has_the_clear_color() {
+    let Some(mut state) = common::headless_state(67, 33, StateConfig::default()) else {
+        return;
+    };
+    state.set_clear_color(CLEAR_COLOR);
+    let frame = state.capture_frame().block_on().unwrap();
+    assert_eq!((frame.width, frame.height), (67, 33));
+    assert_eq!(frame.rgba.len(), 67 * 33 * 4);
+    // The corners, the `triangle` feature draws into the middle.
+    for (x, y) in [(0, 0), (66, 0), (0, 32), (66, 32)] {
+        assert_eq!(frame.pixel(x, y), [255, 0, 255, 255]);
+    }
+}
+
+#[test]
+fn bgra_captures_are_rgba() {
+    let Some(mut state) = common::headless_state_with_format(
+        4,
+        4,
+        wgpu::TextureFormat::Bgra8Unorm,
+        StateConfig::default(),
+    ) else {
+        return;
+    };
+    state.set_clear_color(wgpu::Color {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    });
+    let frame = state.capture_frame().block_on().unwrap();
+    assert_eq!(frame.pixel(3, 3), [255, 0, 0, 255]);
+}
+
+#[test]
+fn capture_matches_the_rendered_frame() {
+    let config = StateConfig {
+        surface_usage: wgpu::TextureUsages::COPY_SRC,
+        sample_count: 4,
+        ..Default::default()
+    };
+    let Some(mut state) = common::headless_state(32, 32, config) else {
+        return;
+    };
+    state.set_clear_color(CLEAR_COLOR);
+    state.render_to_texture();
+    let rendered = common::read_texture(&state, state.headless_texture().unwrap());
+    let frame = state.capture_frame().block_on().unwrap();
+    assert_eq!(frame.rgba, rendered);
+}
+
+/// Records a pass clearing the frame to `color`.
+fn clear(context: &mut RenderContext, color: wgpu::Color) {
+    context
+        .encoder
+        .begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Test Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: context.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+}
+
+#[test]
+fn capture_uses_the_render_fn() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    state.set_render_fn(|context| clear(context, wgpu::Color::GREEN));
+    let frame = state.capture_frame().block_on().unwrap();
+    assert!(frame
+        .rgba
+        .chunks_exact(4)
+        .all(|pixel| pixel == [0, 255, 0, 255]));
+}
+
+#[test]
+fn capture_runs_the_render_graph() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    state
+        .add_pass("overlay", &[RenderGraph::SCENE], |context| {
+            clear(context, wgpu::Color::BLUE)
+        })
+        .unwrap();
+    let frame = state.capture_frame().block_on().unwrap();
+    assert_eq!(frame.pixel(4, 4), [0, 0, 255, 255]);
+}
+
+#[test]
+fn capture_matches_the_rendered_frame_with_fxaa() {
+    let config = StateConfig {
+        surface_usage: wgpu::TextureUsages::COPY_SRC,
+        ..Default::default()
+    };
+    let Some(mut state) = common::headless_state(32, 32, config) else {
+        return;
+    };
+    state.set_clear_color(CLEAR_COLOR);
+    state.set_fxaa(true);
+    state.render_to_texture();
+    let rendered = common::read_texture(&state, state.headless_texture().unwrap());
+    let frame = state.capture_frame().block_on().unwrap();
+    assert_eq!(frame.rgba, rendered);
+    // The smoothing only shows on the edges of the triangle.
+    #[cfg(feature = "triangle")]
+    {
+        state.set_fxaa(false);
+        assert_ne!(state.capture_frame().block_on().unwrap().rgba, rendered);
+    }
+}
+
+#[test]
+fn capture_writes_no_occlusion_queries() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    state.set_occlusion_query_count(4);
+    let has_queries = Rc::new(Cell::new(None));
+    let seen = has_queries.clone();
+    state.set_render_fn(move |context| {
+        seen.set(Some(context.occlusion_query_set().is_some()));
+        context.draw_scene();
+    });
+    state.capture_frame().block_on().unwrap();
+    assert_eq!(has_queries.get(), Some(false));
+    // Frames that are submitted normally still get them.
+    state.render_to_texture();
+    assert_eq!(has_queries.get(), Some(true));
+}
+
+#[test]
+fn only_8_bit_formats_can_be_captured() {
+    let Some(mut state) = common::headless_state_with_format(
+        4,
+        4,
+        wgpu::TextureFormat::Rgba16Float,
+        StateConfig::default(),
+    ) else {
+        return;
+    };
+    assert!(matches!(
+        state.capture_frame().block_on(),
+        Err(CaptureError::UnsupportedFormat(
+            wgpu::TextureFormat::Rgba16Float
+        ))
+    ));
+}
+
+#[test]
+fn captures_encode_as_png() {
+    let Some(mut state) = common::headless_state(5, 3, StateConfig::default()) else {
+        return;
+    };
+    let png = state.capture_frame().block_on().unwrap().encode_png();
+    assert_eq!(
+        png[..8],
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']
+    );
+    // The width and height in the IHDR chunk.
+    assert_eq!(png[16..24], [0, 0, 0, 5, 0, 0, 0, 3]);
+}
@@ -0,0 +1,69 @@
+//! Helpers shared by the integration tests, which need a GPU. They are skipped where no
+//! adapter is available, e.g. in CI without a software renderer.
+
+#![allow(dead_code)]
+
+use pollster::FutureExt;
+use winit_wgpu_min::{State, StateConfig, StateError};
+
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// A headless state of `width` by `height` pixels in `FORMAT`, `None` without an adapter.
+pub fn headless_state(width: u32, height: u32, config: StateConfig) -> Option<State> {
+    headless_state_with_format(width, height, FORMAT, config)
+}
+
+pub fn headless_state_with_format(
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    config: StateConfig,
+) -> Option<State> {
+    match State::new_headless_async(width, height, format, config).block_on() {
+        Ok(state) => Some(state),
+        Err(StateError::NoAdapter) => {
+            eprintln!("No adapter available, skipping the test");
+            None
+        }
+        Err(err) => panic!("Failed to create the headless state: {err}"),
+    }
+}
+
+/// Copies `texture` back to the CPU as tightly packed RGBA rows, it needs `COPY_SRC`.
+pub fn read_texture(state: &State, texture: &wgpu::Texture) -> Vec<u8> {
+    let width = texture.width();
+    let height = texture.height();
+    let unpadded_bytes_per_row = width * 4;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = state.device().create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Test Readback Buffer"),
+        size: padded_bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = state
+        .device()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    state.queue().submit(std::iter::once(encoder.finish()));
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    state.device().poll(wgpu::Maintain::Wait);
+    let data = slice.get_mapped_range();
+    data.chunks_exact(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+        .copied()
+        .collect()
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "compute")]
+
+mod common;
+
+use std::panic::{self, AssertUnwindSafe};
+
+use winit_wgpu_min::{ComputePass, StateConfig};
+
+const DOUBLE: &str = "
+@group(0) @binding(0) var<storage, read_write> values: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < arrayLength(&values) {
+        values[id.x] *= 2u;
+    }
+}
+";
+
+#[test]
+fn compute_pass_doubles_the_storage_buffer() {
+    let Some(state) = common::headless_state(1, 1, StateConfig::default()) else {
+        return;
+    };
+    let input: Vec<u32> = (0..100).collect();
+    let mut pass = ComputePass::new(
+        state.gpu().clone(),
+        DOUBLE,
+        "main",
+        bytemuck::cast_slice(&input),
+    );
+    pass.dispatch(input.len().div_ceil(64) as u32, 1, 1);
+    let output = pollster::block_on(pass.read()).unwrap();
+    let doubled: Vec<u32> = input.iter().map(|value| value * 2).collect();
+    assert_eq!(bytemuck::cast_slice::<u8, u32>(&output), doubled);
+}
+
+#[test]
+fn empty_storage_buffers_are_rejected() {
+    let Some(state) = common::headless_state(1, 1, StateConfig::default()) else {
+        return;
+    };
+    let gpu = state.gpu().clone();
+    let panic = panic::catch_unwind(AssertUnwindSafe(|| {
+        ComputePass::new(gpu, DOUBLE, "main", &[])
+    }))
+    .err()
+    .unwrap();
+    assert_eq!(
+        panic.downcast_ref::<&str>(),
+        Some(&"the storage buffer of a compute pass can't be empty")
+    );
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use std::sync::Arc;
+
+use winit_wgpu_min::{Rect, State, StateConfig, StateError, TextureOptions};
+
+fn readable_config() -> StateConfig {
+    StateConfig {
+        surface_usage: wgpu::TextureUsages::COPY_SRC,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn headless_frame_has_the_clear_color() {
+    let Some(mut state) = common::headless_state(67, 33, readable_config()) else {
+        return;
+    };
+    state.set_clear_color(wgpu::Color {
+        r: 1.0,
+        g: 0.0,
+        b: 1.0,
+        a: 1.0,
+    });
+    assert!(state.render_to_texture().is_some());
+    let texture = state.headless_texture().unwrap();
+    assert_eq!((texture.width(), texture.height()), (67, 33));
+    let rgba = common::read_texture(&state, texture);
+    assert_eq!(rgba.len(), 67 * 33 * 4);
+    // The top left and bottom right pixel, the rows aren't padded anymore.
+    assert_eq!(rgba[..4], [255, 0, 255, 255]);
+    assert_eq!(rgba[rgba.len() - 4..], [255, 0, 255, 255]);
+}
+
+#[test]
+fn zero_sized_headless_state_renders() {
+    let Some(mut state) = common::headless_state(0, 0, readable_config()) else {
+        return;
+    };
+    let texture = state.headless_texture().unwrap();
+    assert!(texture.width() >= 1 && texture.height() >= 1);
+    assert!(state.render_to_texture().is_some());
+}
+
+#[test]
+fn clear_color_is_read_back() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    let default = wgpu::Color {
+        r: 0.1,
+        g: 0.2,
+        b: 0.3,
+        a: 1.0,
+    };
+    assert_eq!(state.clear_color(), default);
+    assert_eq!(state.load_op(), wgpu::LoadOp::Clear(default));
+
+    state.set_clear_color(wgpu::Color::RED);
+    assert_eq!(state.clear_color(), wgpu::Color::RED);
+    assert_eq!(state.load_op(), wgpu::LoadOp::Clear(wgpu::Color::RED));
+
+    // While the previous frame is loaded the color is only stored.
+    state.set_load_op(wgpu::LoadOp::Load);
+    state.set_clear_color(wgpu::Color::GREEN);
+    assert_eq!(state.clear_color(), wgpu::Color::GREEN);
+    assert_eq!(state.load_op(), wgpu::LoadOp::Load);
+    state.set_load_op(wgpu::LoadOp::Clear(wgpu::Color::BLUE));
+    assert_eq!(state.clear_color(), wgpu::Color::BLUE);
+}
+
+#[test]
+fn blocking_headless_frames_are_timed() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    let frame_index = state.frame_index();
+    assert!(state.render_once_blocking().unwrap().is_some());
+    assert_eq!(state.frame_index(), frame_index + 1);
+}
+
+#[test]
+fn headless_texture_gets_the_configured_usage() {
+    let Some(mut state) = common::headless_state(8, 8, readable_config()) else {
+        return;
+    };
+    let usage = state.headless_texture().unwrap().usage();
+    assert_eq!(
+        usage,
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+    );
+    // Kept when the texture is recreated.
+    state.resize(winit::dpi::PhysicalSize::new(16, 16));
+    assert_eq!(state.headless_texture().unwrap().usage(), usage);
+}
+
+#[test]
+fn unsupported_headless_usage_is_an_error() {
+    // sRGB formats can't be storage textures.
+    let config = StateConfig {
+        surface_usage: wgpu::TextureUsages::STORAGE_BINDING,
+        ..Default::default()
+    };
+    let result = pollster::block_on(State::new_headless_async(
+        8,
+        8,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+        config,
+    ));
+    if let Err(StateError::NoAdapter) = result {
+        eprintln!("No adapter available, skipping the test");
+        return;
+    }
+    assert!(matches!(
+        result,
+        Err(StateError::UnsupportedSurfaceUsage(_))
+    ));
+}
+
+#[test]
+fn no_backends_is_no_adapter() {
+    // The variable overrides the backends.
+    if std::env::var_os("WGPU_BACKEND").is_some() {
+        return;
+    }
+    let config = StateConfig {
+        backends: wgpu::Backends::empty(),
+        ..Default::default()
+    };
+    let result = pollster::block_on(State::new_headless_async(8, 8, common::FORMAT, config));
+    assert!(matches!(result, Err(StateError::NoAdapter)));
+}
+
+#[test]
+fn depth_texture_follows_the_sample_count() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    assert_eq!(state.depth_texture().sample_count(), 1);
+    // 4 samples are guaranteed for the formats used here.
+    assert_eq!(state.set_sample_count(4), 4);
+    assert_eq!(state.sample_count(), 4);
+    assert_eq!(state.depth_texture().sample_count(), 4);
+    assert!(state.render_to_texture().is_some());
+
+    assert_eq!(state.set_sample_count(1), 1);
+    assert_eq!(state.depth_texture().sample_count(), 1);
+    assert!(state.render_to_texture().is_some());
+}
+
+#[test]
+fn default_config_requests_no_optional_features() {
+    let Some(state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    let features = state.gpu().device().features() - wgpu::Features::TIMESTAMP_QUERY;
+    assert_eq!(features, wgpu::Features::empty());
+}
+
+#[test]
+fn frame_latency_is_clamped_to_the_backend() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    assert_eq!(state.frame_latency(), 2);
+    assert_eq!(state.effective_frame_latency(), 2);
+    state.set_frame_latency(20);
+    assert_eq!(state.frame_latency(), 20);
+    let max = match state.adapter_info().backend {
+        wgpu::Backend::Dx12 => 16,
+        wgpu::Backend::Metal => 2,
+        wgpu::Backend::Gl => 2,
+        _ => 3,
+    };
+    assert_eq!(state.effective_frame_latency(), max);
+}
+
+#[test]
+fn bind_group_layouts_are_cached() {
+    let Some(state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    let entries = [wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    }];
+    let descriptor = |label| wgpu::BindGroupLayoutDescriptor {
+        label,
+        entries: &entries,
+    };
+    let layout = state.get_or_create_bind_group_layout(&descriptor(Some("Sampler")));
+    let cached = state.get_or_create_bind_group_layout(&descriptor(Some("Sampler")));
+    assert!(Arc::ptr_eq(&layout, &cached));
+    // Another label gets its own layout.
+    let relabeled = state.get_or_create_bind_group_layout(&descriptor(Some("Other Sampler")));
+    assert!(!Arc::ptr_eq(&layout, &relabeled));
+    let unlabeled = state.get_or_create_bind_group_layout(&descriptor(None));
+    assert!(!Arc::ptr_eq(&layout, &unlabeled));
+}
+
+#[test]
+fn sprites_are_batched_by_texture() {
+    let Some(mut state) = common::headless_state(8, 8, StateConfig::default()) else {
+        return;
+    };
+    let load = |state: &State| {
+        state
+            .load_texture(&[255; 4], 1, 1, TextureOptions::default())
+            .unwrap()
+    };
+    let (first, second) = (load(&state), load(&state));
+    let dest = Rect::new(0.0, 0.0, 4.0, 4.0);
+    state.draw_sprite(&first, dest, [1.0; 4]);
+    state.draw_sprite(&second, dest, [1.0; 4]);
+    state.draw_sprite(&first, dest, [1.0; 4]);
+    assert!(state.render_to_texture().is_some());
+    assert_eq!(state.sprite_draw_calls(), 2);
+
+    state.draw_sprite(&first, dest, [1.0; 4]);
+    state.draw_sprite(&first, dest, [1.0; 4]);
+    assert!(state.render_to_texture().is_some());
+    assert_eq!(state.sprite_draw_calls(), 1);
+}
+
+#[cfg(feature = "text")]
+#[test]
+fn text_is_drawn_with_the_font() {
+    let Ok(font) = std::fs::read("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf") else {
+        return;
+    };
+    let Some(mut state) = common::headless_state(32, 32, readable_config()) else {
+        return;
+    };
+    assert!(state.set_font(b"not a font".to_vec(), 24.0).is_err());
+    state.set_clear_color(wgpu::Color::BLACK);
+    let render = |state: &mut State| {
+        assert!(state.render_to_texture().is_some());
+        common::read_texture(state, state.headless_texture().unwrap())
+    };
+    let scene = render(&mut state);
+    // Nothing to draw with yet.
+    state.draw_text("W", 4.0, 4.0, [1.0; 4]);
+    assert_eq!(render(&mut state), scene);
+
+    state.set_font(font, 24.0).unwrap();
+    state.draw_text("W", 4.0, 4.0, [1.0; 4]);
+    let text = render(&mut state);
+    assert!(text.chunks(4).any(|pixel| pixel == [255, 255, 255, 255]));
+    assert_ne!(text, scene);
+    // The glyphs are drawn for one frame only.
+    assert_eq!(render(&mut state), scene);
+}